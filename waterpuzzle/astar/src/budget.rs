@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+/// 탐색에 쓸 수 있는 한도. 한도에 닿으면 솔버는 그때까지 찾은 가장 나은 경로를 돌려준다.
+/// 찾은 목표가 있으면 그 목표까지의 경로를, 없으면 h가 가장 작았던 상태까지의 경로를 돌려주며,
+/// 어느 쪽도 가장 짧다는 보장이 없다(`SolverStrategy::budget_exhausted()`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolveBudget {
+    /// 펼칠(expand) 수 있는 상태의 개수. None이면 한도가 없다.
+    pub max_nodes: Option<usize>,
+    /// None이면 한도가 없다.
+    pub max_time: Option<Duration>,
+}
+
+impl SolveBudget {
+    pub const UNLIMITED: Self = Self {
+        max_nodes: None,
+        max_time: None,
+    };
+
+    pub fn with_max_nodes(self, max_nodes: usize) -> Self {
+        Self {
+            max_nodes: Some(max_nodes),
+            ..self
+        }
+    }
+
+    pub fn with_max_time(self, max_time: Duration) -> Self {
+        Self {
+            max_time: Some(max_time),
+            ..self
+        }
+    }
+
+    /// expanded개를 펼치고 elapsed만큼 지났을 때 한도에 닿았으면 true
+    pub fn is_exhausted(&self, expanded: usize, elapsed: Duration) -> bool {
+        self.max_nodes
+            .is_some_and(|max_nodes| expanded >= max_nodes)
+            || self.max_time.is_some_and(|max_time| elapsed >= max_time)
+    }
+}
@@ -1,146 +1,186 @@
-use std::{
-    collections::{BinaryHeap, HashMap},
-    rc::Rc,
-    sync::mpsc,
-};
+use std::{collections::BinaryHeap, sync::mpsc};
 
 use state::state::State;
 
-use crate::node::Node;
+use crate::{
+    heuristic::{BoundaryCount, Heuristic},
+    node::Node,
+    stats::{SolveOutcome, SolveStats},
+    strategy::SolveControl,
+    visited::{StateId, Visited},
+};
 
 #[derive(Debug, Default)]
-pub struct ExaustiveAStar {
-    open_set: BinaryHeap<Node>,
-    // came_from과 g_score 맵은 이제 Rc<State>를 키로 사용
-    came_from: HashMap<Rc<State>, Rc<State>>,
-    g_score: HashMap<Rc<State>, u32>,
-    goal: Option<Rc<State>>,
-    pub message: String,
+pub struct ExaustiveAStar<H = BoundaryCount> {
+    heuristic: H,
+    // open set에는 상태의 번호만 넣고, 펼칠 때 visited의 키에서 State를 다시 만든다.
+    open_set: BinaryHeap<Node<StateId>>,
+    visited: Visited,
+    goal: Option<StateId>,
+    // find_path()에 넘긴 상태. 키에서 만든 상태는 비커의 순서가 다르므로 경로의 처음에는 이것을 쓴다.
+    initial_state: State,
+    /// 펼친(expand) 상태의 개수
+    pub epoch: usize,
+    /// 펼친 상태들에서 `State::useful_moves()`의 개수를 모두 더한 값
+    pub n_successors: usize,
+    /// 쓸모 있는 붓기(`State::useful_moves()`)가 하나도 없는, 목표가 아닌 상태의 개수
+    pub n_dead_ends: usize,
+    /// 펼친 상태의 h 중 가장 작은 값
+    best_h: u32,
+    /// h가 best_h인 상태. 한도에 닿으면 이 상태까지의 경로를 돌려준다.
+    best_state: Option<StateId>,
+    pub(crate) control: SolveControl,
 }
 
 impl ExaustiveAStar {
     pub fn new() -> Self {
         Default::default()
     }
+}
+
+impl<H: Heuristic> ExaustiveAStar<H> {
+    pub fn with_heuristic(heuristic: H) -> Self {
+        Self {
+            heuristic,
+            open_set: BinaryHeap::new(),
+            visited: Visited::default(),
+            goal: None,
+            initial_state: State::default(),
+            epoch: 0,
+            n_successors: 0,
+            n_dead_ends: 0,
+            best_h: u32::MAX,
+            best_state: None,
+            control: SolveControl::default(),
+        }
+    }
+
+    fn current_stats(&self) -> SolveStats {
+        SolveStats {
+            expanded: self.epoch,
+            frontier: self.open_set.len(),
+            visited: self.visited.len(),
+            best_h: self.best_h,
+            elapsed: self.control.reporter.elapsed(),
+            outcome: SolveOutcome::Searching,
+        }
+    }
 
     pub fn find_path(
         &mut self,
         initial_state: State,
         stop_rx: mpsc::Receiver<()>,
     ) -> Option<Vec<State>> {
-        let initial_arc_state = Rc::new(initial_state); // 초기 상태를 Arc로 감쌈
-
-        // Arc의 clone()을 호출하면 참조 카운터만 증가한다.
-        self.open_set.push(Node::new(initial_arc_state.clone(), 0));
-        self.g_score.insert(initial_arc_state.clone(), 0);
+        self.control.start();
+        // 앞의 find_path()가 남긴 것을 지운다. visited는 insert_initial()이 비운다.
+        self.open_set.clear();
+        self.goal = None;
+        self.best_state = None;
+        self.epoch = 0;
+        self.n_successors = 0;
+        self.n_dead_ends = 0;
+        let config = initial_state.config();
+
+        let initial_id = self.visited.insert_initial(initial_state.key());
+        let h = self.heuristic.estimate(&initial_state);
+        self.best_h = h;
+        self.open_set.push(Node::new(initial_id, 0, h));
+        self.initial_state = initial_state;
+        let mut budget_exhausted = false;
 
-        let mut epoch = 0;
         while let Some(current_node) = self.open_set.pop() {
             if stop_rx.try_recv().is_ok() {
-                self.message = "Stopped".to_string();
+                self.control
+                    .finish(self.current_stats(), SolveOutcome::Stopped);
                 return None;
             }
-            let current_arc_state = current_node.state;
+            if self.control.is_exhausted(self.epoch) {
+                budget_exhausted = true;
+                break;
+            }
+            // open set은 f가 작은 것부터 꺼내므로, 찾은 목표보다 짧은 경로는 더 나올 수 없다.
+            // h가 admissible할 때만 맞다. See `Heuristic`.
+            if let Some(goal) = self.goal
+                && current_node.f() >= self.visited.g(goal)
+            {
+                break;
+            }
+            if current_node.h() < self.best_h {
+                self.best_h = current_node.h();
+                self.best_state = Some(current_node.state);
+            }
+            if self.control.reporter.is_due() {
+                self.control.reporter.send(self.current_stats());
+            }
+            let current_id = current_node.state;
+
+            // A*의 g_score 검사 (중복 처리). 상태를 다시 만들기 전에 거른다.
+            if current_node.g > self.visited.g(current_id) {
+                continue;
+            }
+            let current_state = self.visited.state(current_id, config);
 
-            if current_arc_state.is_completed() {
+            if current_state.is_search_goal() {
                 match self.goal.as_ref() {
-                    Some(goal) => {
-                        let g_goal = self.g_score.get(goal).unwrap();
-                        let g_current = self
-                            .g_score
-                            .get(&current_arc_state)
-                            .expect("State has no g value");
-                        if g_current < g_goal {
-                            self.goal = Some(current_arc_state);
+                    Some(&goal) => {
+                        if self.visited.g(current_id) < self.visited.g(goal) {
+                            self.goal = Some(current_id);
                         }
                     }
-                    None => self.goal = Some(current_arc_state),
+                    None => self.goal = Some(current_id),
                 }
                 continue;
             }
 
-            // A*의 g_score 검사 (중복 처리)
-            if let Some(&existing_g) = self.g_score.get(&current_arc_state) {
-                if current_node.g > existing_g {
-                    continue;
-                }
-            }
-
-            // NextStateIterator를 사용하거나 직접 루프를 돌 수 있습니다.
-            // 여기서는 NextStateIterator를 가정합니다. (State::new()의 capacities 인자 때문에 약간의 수정 필요)
-            // NextStateIterator는 &State를 받으므로, Rc::deref()를 사용하여 &State로 변환 가능
-            // for neighbor_state in NextStateIterator::new(&current_arc_state) {
-            // ...
-            // }
-
-            epoch += 1;
+            self.epoch += 1;
             let next_g = current_node.g + 1;
-
-            // 직접 루프 사용
-            for i in 0..current_arc_state.beakers.len() {
-                for j in 0..current_arc_state.beakers.len() {
-                    if i == j {
+            let n_successors = self.n_successors;
+
+            // 결과가 같은 붓기는 하나만 본다. See `State::useful_moves()`.
+            for mv in current_state.useful_moves() {
+                self.n_successors += 1;
+                let mut neighbor_state = current_state.clone();
+                let _ = neighbor_state.apply(mv);
+
+                if let Some(neighbor_id) =
+                    self.visited.relax(neighbor_state.key(), next_g, current_id)
+                {
+                    let h = self.heuristic.estimate(&neighbor_state);
+                    if let Some(goal) = self.goal
+                        && next_g + h >= self.visited.g(goal)
+                    {
                         continue;
                     }
-
-                    if current_arc_state.can_pour(i, j) {
-                        // Rc::make_mut()는 참조 카운트가 1이 아니면 복사본을 생성합니다.
-                        // 하지만 여기서는 새로운 상태를 만들어야 하므로 그냥 clone()을 호출하고 pour 합니다.
-                        // Rc<State>를 Clone()하면 참조 카운트만 증가합니다.
-                        // 실제 상태를 변경하려면 Rc 내부의 State를 mutable하게 만들어야 합니다.
-                        // 따라서, 새로운 상태를 만들 때는 Rc::clone()이 아니라 State::clone()을 해야 합니다.
-                        // current_arc_state는 Rc<State>이므로 *current_arc_state는 &State 입니다.
-                        // 따라서 (*current_arc_state).clone()으로 State 자체를 복사합니다.
-
-                        let mut neighbor_state = (*current_arc_state).clone(); // State를 깊은 복사
-                        neighbor_state.pour(i, j); // 복사된 State 변경
-
-                        let neighbor_arc_state = Rc::new(neighbor_state); // 변경된 State를 다시 Arc로 감쌈
-
-                        if next_g
-                            < *self
-                                .g_score
-                                .get(&neighbor_arc_state)
-                                .unwrap_or(&std::u32::MAX)
-                        {
-                            self.g_score.insert(neighbor_arc_state.clone(), next_g);
-                            self.came_from
-                                .insert(neighbor_arc_state.clone(), current_arc_state.clone());
-
-                            let neighbor_node = Node::new(neighbor_arc_state.clone(), next_g);
-                            self.open_set.push(neighbor_node);
-                        }
-                    }
+                    self.open_set.push(Node::new(neighbor_id, next_g, h));
                 }
             }
+            if self.n_successors == n_successors {
+                self.n_dead_ends += 1;
+            }
         }
-        match self.goal.as_ref() {
+        if budget_exhausted {
+            self.control
+                .finish(self.current_stats(), SolveOutcome::BudgetExhausted);
+            return self.partial_path();
+        }
+        match self.goal {
             Some(goal) => {
-                let path = self.construct_path_from(goal.clone());
-                self.message = format!(
-                    "Path length: {}, Epoch: {epoch}, Size of g_score: {}",
-                    path.len() - 1,
-                    self.g_score.len()
-                );
-                Some(path)
+                self.control
+                    .finish(self.current_stats(), SolveOutcome::Found);
+                Some(self.visited.path_to(goal, &self.initial_state))
             }
             None => {
-                self.message = format!(
-                    "Fail to find a path, Epoch: {epoch}, Size of g_score: {}",
-                    self.g_score.len()
-                );
+                self.control
+                    .finish(self.current_stats(), SolveOutcome::NoPath);
                 None
             }
         }
     }
 
-    fn construct_path_from(&self, mut arc_state: Rc<State>) -> Vec<State> {
-        let mut path = vec![(*arc_state).clone()]; // Rc<State>에서 State를 복사
-        while self.came_from.contains_key(&arc_state) {
-            arc_state = self.came_from.get(&arc_state).unwrap().clone();
-            path.push((*arc_state).clone());
-        }
-        path.reverse();
-        path
+    /// 한도에 닿았을 때 그때까지 찾은 목표, 없으면 h가 가장 작았던 상태까지의 경로
+    fn partial_path(&self) -> Option<Vec<State>> {
+        self.goal
+            .or(self.best_state)
+            .map(|id| self.visited.path_to(id, &self.initial_state))
     }
 }
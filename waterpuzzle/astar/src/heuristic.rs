@@ -0,0 +1,109 @@
+//! 솔버가 쓰는 목표까지의 거리(붓기 수) 추정.
+//! 추정이 실제 거리보다 크지 않으면(admissible) ExaustiveAStar와 IdaStar가 가장 짧은 경로를 찾는다.
+
+use state::{
+    Water,
+    beaker::{NO_WATER, UNKNOWN_WATER},
+    state::State,
+};
+
+pub trait Heuristic {
+    /// state에서 목표(`State.is_search_goal()`)까지 필요한 붓기 수의 추정
+    fn estimate(&self, state: &State) -> u32;
+}
+
+/// 이웃한 두 층의 색이 다른 경계의 개수(`State.h`). 빈 층과의 경계도 센다.
+/// 빠르게 목표를 찾지만, 붓기 한 번에 2가 줄 수 있으므로 실제 거리보다 클 수 있다.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BoundaryCount;
+
+impl Heuristic for BoundaryCount {
+    fn estimate(&self, state: &State) -> u32 {
+        state.h
+    }
+}
+
+/// 색마다 (덩어리의 개수 - 1)의 합. 덩어리는 한 비커에서 같은 색이 이어진 층들이다.
+/// 붓기 한 번은 덩어리를 많아야 하나 합치므로 실제 거리보다 크지 않다.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ColorFragmentation;
+
+impl Heuristic for ColorFragmentation {
+    fn estimate(&self, state: &State) -> u32 {
+        let runs = Runs::of(state);
+        with_exposure_bound(state, runs.n_runs - runs.n_colors)
+    }
+}
+
+/// 반드시 비커를 떠나야 하는 덩어리의 개수.
+/// 맨 아래가 아닌 덩어리는 아래 층이 다른 색이므로 모두 떠나야 하고, 맨 아래 덩어리의 색이 같은 비커들은
+/// 하나만 남고 모두 떠나야 한다. 붓기 한 번은 많아야 한 덩어리를 옮기므로 실제 거리보다 크지 않으며,
+/// 언제나 ColorFragmentation보다 작지 않다.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinimumPours;
+
+impl Heuristic for MinimumPours {
+    fn estimate(&self, state: &State) -> u32 {
+        let runs = Runs::of(state);
+        with_exposure_bound(state, runs.n_runs - runs.n_bottom_colors)
+    }
+}
+
+/// 덩어리의 개수와 나오는 색의 개수
+struct Runs {
+    n_runs: u32,
+    n_colors: u32,
+    /// 맨 아래 덩어리로 나오는 색의 개수
+    n_bottom_colors: u32,
+}
+
+impl Runs {
+    fn of(state: &State) -> Self {
+        let mut colors = [false; 256];
+        let mut bottom_colors = [false; 256];
+        let mut n_runs = 0;
+        for beaker in state.beakers.iter() {
+            let mut prev = NO_WATER;
+            for order in 0..beaker.n_waters {
+                let water = beaker.water(order);
+                if order == 0 {
+                    bottom_colors[water as usize] = true;
+                }
+                if water != prev {
+                    n_runs += 1;
+                    colors[water as usize] = true;
+                    prev = water;
+                }
+            }
+        }
+        let count = |flags: [bool; 256]| flags.iter().filter(|&&flag| flag).count() as u32;
+        Self {
+            n_runs,
+            n_colors: count(colors),
+            n_bottom_colors: count(bottom_colors),
+        }
+    }
+}
+
+/// 가려진 층(UNKNOWN_WATER)이 top으로 드러나도 목표이므로, 드러나기까지 필요한 붓기 수와 비교해 작은 것을 쓴다.
+/// 가장 위의 가려진 층보다 위에 있는 덩어리는 모두 떠나야 드러난다.
+fn with_exposure_bound(state: &State, bound: u32) -> u32 {
+    state
+        .beakers
+        .iter()
+        .filter_map(|beaker| {
+            let highest = (0..beaker.n_waters)
+                .rev()
+                .find(|&order| beaker.water(order) == UNKNOWN_WATER)?;
+            let mut prev: Water = UNKNOWN_WATER;
+            let mut n_runs = 0;
+            for order in highest + 1..beaker.n_waters {
+                if beaker.water(order) != prev {
+                    n_runs += 1;
+                    prev = beaker.water(order);
+                }
+            }
+            Some(n_runs)
+        })
+        .fold(bound, u32::min)
+}
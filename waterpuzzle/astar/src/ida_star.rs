@@ -0,0 +1,234 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::mpsc,
+};
+
+use state::{key::StateKey, state::State};
+
+use crate::{
+    heuristic::{BoundaryCount, Heuristic},
+    stats::{SolveOutcome, SolveStats},
+    strategy::SolveControl,
+};
+
+/// 한 번의 반복에서 펼친 상태를 기억하는 표에 f의 한계 1마다 넣을 수 있는 상태의 개수의 기본값
+pub const TABLE_ENTRIES_PER_DEPTH: usize = 1 << 10;
+
+/// 한 번의 깊이 우선 탐색 결과
+enum Search {
+    Found,
+    Stopped,
+    OutOfBudget,
+    /// 한계를 넘은 f 중 가장 작은 값. 넘은 것이 없으면 u32::MAX
+    Exceeded(u32),
+}
+
+/// Iterative deepening A*. 지금 경로의 상태들과, 펼친 상태를 기억하는 표만 쓴다.
+/// 표는 한 번의 반복에서 f의 한계(탐색 깊이의 한계) × table_entries_per_depth개까지만 넣으므로, 메모리는
+/// 깊이가 d일 때 (d개의 상태와 키) + (d × table_entries_per_depth개의 키)로 탐색 깊이에 비례한다.
+/// 키는 작은 판에서 64바이트이고 큰 판에서는 8 × 비커의 개수 바이트이므로, 기본값으로 깊이 50을 찾으면 표는
+/// 작은 판에서 4MB, 비커가 20개인 판에서 13MB 남짓이다. 표는 같은 상태를 다시 펼치지 않게 할 뿐이어서
+/// 0으로 꺼도 같은 경로를 찾는다.
+/// 대신 같은 상태를 여러 번 펼치므로 FastAStar, ExaustiveAStar보다 느리다.
+#[derive(Debug)]
+pub struct IdaStar<H = BoundaryCount> {
+    heuristic: H,
+    // 지금 경로의 상태들. path[0]이 처음 상태이다.
+    path: Vec<State>,
+    // 경로 위의 상태로 되돌아가지 않도록 path의 상태를 모아 둔다.
+    on_path: HashSet<StateKey>,
+    // 이번 반복에서 펼친 상태와 그때의 g. 더 큰 g로 다시 만나면 펼치지 않는다. table_limit개까지만 넣는다.
+    // 두 표에는 State 대신 작은 StateKey를 넣는다.
+    expanded: HashMap<StateKey, u32>,
+    /// f의 한계 1마다 expanded에 넣을 수 있는 상태의 개수. 0이면 표를 쓰지 않는다.
+    pub table_entries_per_depth: usize,
+    // 이번 반복에서 expanded에 넣을 수 있는 상태의 개수
+    table_limit: usize,
+    /// 펼친(expand) 상태의 개수. 같은 상태를 여러 번 펼치면 여러 번 센다.
+    pub epoch: usize,
+    /// f의 한계를 늘려 가며 다시 탐색한 횟수
+    pub n_iterations: usize,
+    /// 펼친 상태들에서 `State::useful_moves()`의 개수를 모두 더한 값
+    pub n_successors: usize,
+    /// 쓸모 있는 붓기(`State::useful_moves()`)가 하나도 없는, 목표가 아닌 상태의 개수
+    pub n_dead_ends: usize,
+    /// 펼친 상태의 h 중 가장 작은 값
+    best_h: u32,
+    /// 처음 상태에서 h가 best_h인 상태까지의 경로. 한도에 닿으면 이 경로를 돌려준다.
+    best_path: Vec<State>,
+    // 끝난 탐색의 통계는 표를 비우기 전에 적어 둔다.
+    pub(crate) control: SolveControl,
+}
+
+impl IdaStar {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<H: Heuristic + Default> Default for IdaStar<H> {
+    fn default() -> Self {
+        Self::with_heuristic(H::default())
+    }
+}
+
+impl<H: Heuristic> IdaStar<H> {
+    pub fn with_heuristic(heuristic: H) -> Self {
+        Self {
+            heuristic,
+            path: Vec::new(),
+            on_path: HashSet::new(),
+            expanded: HashMap::new(),
+            table_entries_per_depth: TABLE_ENTRIES_PER_DEPTH,
+            table_limit: 0,
+            epoch: 0,
+            n_iterations: 0,
+            n_successors: 0,
+            n_dead_ends: 0,
+            best_h: u32::MAX,
+            best_path: Vec::new(),
+            control: SolveControl::default(),
+        }
+    }
+
+    fn current_stats(&self) -> SolveStats {
+        SolveStats {
+            expanded: self.epoch,
+            frontier: self.path.len(),
+            visited: self.expanded.len(),
+            best_h: self.best_h,
+            elapsed: self.control.reporter.elapsed(),
+            outcome: SolveOutcome::Searching,
+        }
+    }
+
+    pub fn find_path(
+        &mut self,
+        initial_state: State,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Option<Vec<State>> {
+        self.control.start();
+        // 앞의 find_path()가 멈추라는 신호를 받아 끝났으면 경로가 남아 있다.
+        self.path.clear();
+        self.on_path.clear();
+        self.expanded.clear();
+        self.best_path.clear();
+        self.epoch = 0;
+        self.n_iterations = 0;
+        self.n_successors = 0;
+        self.n_dead_ends = 0;
+        let mut threshold = self.heuristic.estimate(&initial_state);
+        self.best_h = threshold;
+        self.on_path.insert(initial_state.key());
+        self.path.push(initial_state);
+
+        loop {
+            self.n_iterations += 1;
+            self.expanded.clear();
+            self.table_limit = self
+                .table_entries_per_depth
+                .saturating_mul(threshold as usize);
+            let search = self.search(0, threshold, &stop_rx);
+            match search {
+                Search::Found => {
+                    self.control
+                        .finish(self.current_stats(), SolveOutcome::Found);
+                    self.on_path.clear();
+                    self.expanded.clear();
+                    return Some(std::mem::take(&mut self.path));
+                }
+                Search::Stopped => {
+                    self.control
+                        .finish(self.current_stats(), SolveOutcome::Stopped);
+                    return None;
+                }
+                Search::OutOfBudget => {
+                    self.control
+                        .finish(self.current_stats(), SolveOutcome::BudgetExhausted);
+                    self.path.clear();
+                    self.on_path.clear();
+                    self.expanded.clear();
+                    let best_path = std::mem::take(&mut self.best_path);
+                    return (!best_path.is_empty()).then_some(best_path);
+                }
+                Search::Exceeded(u32::MAX) => {
+                    self.control
+                        .finish(self.current_stats(), SolveOutcome::NoPath);
+                    return None;
+                }
+                Search::Exceeded(next_threshold) => threshold = next_threshold,
+            }
+        }
+    }
+
+    /// path의 마지막 상태에서 f가 threshold를 넘지 않는 곳까지 깊이 우선으로 찾는다.
+    /// 목표를 찾으면 path에 목표까지의 경로가 남는다.
+    fn search(&mut self, g: u32, threshold: u32, stop_rx: &mpsc::Receiver<()>) -> Search {
+        let current_state = self.path.last().unwrap();
+        let h = self.heuristic.estimate(current_state);
+        let f = g + h;
+        if f > threshold {
+            return Search::Exceeded(f);
+        }
+        if current_state.is_search_goal() {
+            self.best_h = self.best_h.min(h);
+            return Search::Found;
+        }
+        if stop_rx.try_recv().is_ok() {
+            return Search::Stopped;
+        }
+        if self.control.is_exhausted(self.epoch) {
+            return Search::OutOfBudget;
+        }
+        if h < self.best_h {
+            self.best_h = h;
+            self.best_path = self.path.clone();
+        }
+        if self.control.reporter.is_due() {
+            self.control.reporter.send(self.current_stats());
+        }
+        // 같은 반복에서 g가 같거나 작을 때 이미 펼쳤으면 그때 더 넓게 찾았다.
+        let current_key = current_state.key();
+        match self.expanded.get(&current_key) {
+            Some(&expanded_g) if expanded_g <= g => return Search::Exceeded(u32::MAX),
+            _ => {
+                if self.expanded.len() < self.table_limit {
+                    self.expanded.insert(current_key, g);
+                }
+            }
+        }
+
+        self.epoch += 1;
+        let mut neighbor_states = Vec::new();
+        // 결과가 같은 붓기는 하나만 본다. See `State::useful_moves()`.
+        for mv in current_state.useful_moves() {
+            let mut neighbor_state = current_state.clone();
+            let _ = neighbor_state.apply(mv);
+            neighbor_states.push(neighbor_state);
+        }
+        self.n_successors += neighbor_states.len();
+        if neighbor_states.is_empty() {
+            self.n_dead_ends += 1;
+        }
+        // h가 작은 쪽부터 찾으면 목표를 일찍 만난다.
+        neighbor_states
+            .sort_by_cached_key(|neighbor_state| self.heuristic.estimate(neighbor_state));
+
+        let mut min_exceeded = u32::MAX;
+        for neighbor_state in neighbor_states {
+            let neighbor_key = neighbor_state.key();
+            if self.on_path.contains(&neighbor_key) {
+                continue;
+            }
+            self.on_path.insert(neighbor_key);
+            self.path.push(neighbor_state);
+            match self.search(g + 1, threshold, stop_rx) {
+                Search::Exceeded(exceeded) => min_exceeded = min_exceeded.min(exceeded),
+                result => return result,
+            }
+            let neighbor_state = self.path.pop().unwrap();
+            self.on_path.remove(&neighbor_state.key());
+        }
+        Search::Exceeded(min_exceeded)
+    }
+}
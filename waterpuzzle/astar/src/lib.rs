@@ -1,11 +1,10 @@
-#[allow(unused_imports)]
-use crate::fast_astar::FastAStar;
-#[allow(unused_imports)]
-use crate::exaustive_astar::ExaustiveAStar;
-
-// pub type Astar = FastAStar;
-pub type Astar = ExaustiveAStar;
-
 pub mod node;
+pub mod budget;
+pub mod heuristic;
 pub mod fast_astar;
-pub mod exaustive_astar;
\ No newline at end of file
+pub mod exaustive_astar;
+pub mod ida_star;
+pub mod parallel_astar;
+pub mod stats;
+pub mod strategy;
+pub mod visited;
\ No newline at end of file
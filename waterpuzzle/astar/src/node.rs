@@ -1,22 +1,28 @@
-use std::rc::Rc;
-
-use state::state::State;
-
+/// S는 상태를 가리키는 값이다. 한 스레드에서는 `Visited`의 번호, 여러 스레드에서는 `State::key()`를 쓴다.
 #[derive(Clone, Debug, Eq)]
-pub struct Node {
-    pub state: Rc<State>,
+pub struct Node<S> {
+    pub state: S,
     pub g: u32,
     f: u32,
 }
 
-impl Node {
-    pub(crate) fn new(state: Rc<State>, g: u32) -> Self {
-        let f = g + state.h;
+impl<S> Node<S> {
+    /// h: state에서 목표까지의 추정. See `Heuristic`.
+    pub(crate) fn new(state: S, g: u32, h: u32) -> Self {
+        let f = g + h;
         Self { state, g, f }
     }
+
+    pub fn h(&self) -> u32 {
+        self.f - self.g
+    }
+
+    pub fn f(&self) -> u32 {
+        self.f
+    }
 }
 
-impl PartialEq for Node {
+impl<S: PartialEq> PartialEq for Node<S> {
     fn eq(&self, other: &Self) -> bool {
         self.state == other.state
     }
@@ -24,7 +30,7 @@ impl PartialEq for Node {
 
 // BinaryHeap이 Node를 비교할 수 있도록 Ord 트레이트 구현
 // f_cost가 가장 작은 노드가 "가장 작다"고 간주되어 pop될 수 있도록 합니다.
-impl Ord for Node {
+impl<S: Eq> Ord for Node<S> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         // f_cost를 기준으로 비교하되, 최소 힙처럼 동작하도록 역순으로 비교합니다.
         // 즉, f_cost가 작은 것이 더 "크다"고 간주되어 BinaryHeap에서 먼저 pop되도록 합니다.
@@ -45,7 +51,7 @@ impl Ord for Node {
 }
 
 // PartialOrd도 구현해야 합니다.
-impl PartialOrd for Node {
+impl<S: Eq> PartialOrd for Node<S> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
     }
@@ -0,0 +1,340 @@
+use std::{
+    collections::{BinaryHeap, HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::Duration,
+};
+
+use state::{config::PuzzleConfig, key::StateKey, state::State};
+
+use crate::{
+    heuristic::{BoundaryCount, Heuristic},
+    node::Node,
+    stats::{Reporter, SolveOutcome, SolveStats},
+    strategy::SolveControl,
+};
+
+/// 다른 스레드에게 넘기는 상태. (상태의 키, g, h, 부모의 키)
+/// h는 상태를 가진 보내는 쪽에서 구한다. 받는 쪽은 상태를 펼칠 때에만 키에서 다시 만든다.
+type Message = (StateKey, u32, u32, Option<StateKey>);
+/// 스레드의 받은 편지함에 넣는 것. None은 상태 없이 기다리는 스레드를 깨운다. See `Shared::wake_all()`.
+type Mail = Option<Message>;
+/// 상태마다 지금까지 찾은 가장 작은 g와 그때의 부모
+type GScore = HashMap<StateKey, (u32, Option<StateKey>)>;
+
+/// 스레드들이 함께 쓰는 값
+struct Shared<'a, H> {
+    heuristic: &'a H,
+    config: PuzzleConfig,
+    senders: Vec<mpsc::Sender<Mail>>,
+    // 어느 스레드의 open set이나 채널에 있는, 아직 처리하지 않은 상태의 개수. 0이 되면 탐색이 끝난다.
+    n_pending: AtomicUsize,
+    stop: AtomicBool,
+    // 지금까지 찾은 목표의 가장 작은 g. f = g + h가 이보다 작지 않은 상태는 펼치지 않는다.
+    best_g: AtomicU32,
+    goal: Mutex<Option<StateKey>>,
+    epoch: AtomicUsize,
+    n_visited: AtomicUsize,
+    best_h: AtomicU32,
+    // h가 가장 작았던 상태와 그 h. 한도에 닿으면 이 상태까지의 경로를 돌려준다.
+    best_state: Mutex<Option<(u32, StateKey)>>,
+    n_successors: AtomicUsize,
+    n_dead_ends: AtomicUsize,
+}
+
+impl<H> Shared<'_, H> {
+    /// 상태의 해시로 그 상태를 맡을 스레드를 정한다. 같은 상태는 언제나 같은 스레드가 맡는다.
+    fn owner(&self, key: &StateKey) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.senders.len() as u64) as usize
+    }
+
+    fn send(&self, message: Message) {
+        self.n_pending.fetch_add(1, Ordering::SeqCst);
+        let owner = self.owner(&message.0);
+        // 받는 쪽은 모든 스레드가 끝날 때까지 살아 있다.
+        let _ = self.senders[owner].send(Some(message));
+    }
+
+    fn done_one(&self) {
+        if self.n_pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // 탐색이 끝났으므로 받을 상태를 기다리는 스레드들이 끝나도록 깨운다.
+            self.wake_all();
+        }
+    }
+
+    /// 받을 상태를 기다리며 멈춰 있는 스레드들이 n_pending과 stop을 다시 보게 한다.
+    fn wake_all(&self) {
+        for sender in &self.senders {
+            let _ = sender.send(None);
+        }
+    }
+
+    fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.wake_all();
+    }
+
+    fn stats(&self, reporter: &Reporter) -> SolveStats {
+        SolveStats {
+            expanded: self.epoch.load(Ordering::Relaxed),
+            frontier: self.n_pending.load(Ordering::Relaxed),
+            visited: self.n_visited.load(Ordering::Relaxed),
+            best_h: self.best_h.load(Ordering::Relaxed),
+            elapsed: reporter.elapsed(),
+            outcome: SolveOutcome::Searching,
+        }
+    }
+}
+
+/// 상태를 해시로 나누어 스레드마다 자기 open set에서 펼치는 A*(hash distributed A*).
+/// 찾은 목표의 g보다 f가 작은 상태를 모두 살펴보므로, h가 admissible하면 ExaustiveAStar와 같은 길이의
+/// 가장 짧은 경로를 찾는다. 받을 상태가 없는 스레드는 채널에서 기다린다.
+#[derive(Default)]
+pub struct ParallelAStar<H = BoundaryCount> {
+    heuristic: H,
+    /// 0이면 쓸 수 있는 코어의 개수만큼 스레드를 만든다.
+    pub n_threads: usize,
+    /// 펼친(expand) 상태의 개수
+    pub epoch: usize,
+    /// 펼친 상태들에서 `State::useful_moves()`의 개수를 모두 더한 값
+    pub n_successors: usize,
+    /// 쓸모 있는 붓기(`State::useful_moves()`)가 하나도 없는, 목표가 아닌 상태의 개수
+    pub n_dead_ends: usize,
+    // 한도는 스레드들을 지켜보는 쪽에서 몇 ms마다 확인하므로 max_nodes를 조금 넘겨 펼칠 수 있다.
+    pub(crate) control: SolveControl,
+}
+
+impl ParallelAStar {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<H: Heuristic + Sync> ParallelAStar<H> {
+    pub fn with_heuristic(heuristic: H) -> Self {
+        Self {
+            heuristic,
+            n_threads: 0,
+            epoch: 0,
+            n_successors: 0,
+            n_dead_ends: 0,
+            control: SolveControl::default(),
+        }
+    }
+
+    pub fn find_path(
+        &mut self,
+        initial_state: State,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Option<Vec<State>> {
+        self.control.start();
+        let mut budget_exhausted = false;
+        let n_threads = match self.n_threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            (0..n_threads).map(|_| mpsc::channel()).unzip();
+        let initial_h = self.heuristic.estimate(&initial_state);
+        let shared = Shared {
+            heuristic: &self.heuristic,
+            config: initial_state.config(),
+            senders,
+            n_pending: AtomicUsize::new(0),
+            stop: AtomicBool::new(false),
+            best_g: AtomicU32::new(u32::MAX),
+            goal: Mutex::new(None),
+            epoch: AtomicUsize::new(0),
+            n_visited: AtomicUsize::new(0),
+            best_h: AtomicU32::new(initial_h),
+            best_state: Mutex::new(None),
+            n_successors: AtomicUsize::new(0),
+            n_dead_ends: AtomicUsize::new(0),
+        };
+        shared.send((initial_state.key(), 0, initial_h, None));
+
+        let g_scores: Vec<GScore> = thread::scope(|scope| {
+            let workers: Vec<_> = receivers
+                .into_iter()
+                .map(|inbox| scope.spawn(|| Self::work(&shared, inbox)))
+                .collect();
+            while workers.iter().any(|worker| !worker.is_finished()) {
+                if self.control.reporter.is_due() {
+                    self.control
+                        .reporter
+                        .send(shared.stats(&self.control.reporter));
+                }
+                if !budget_exhausted
+                    && self
+                        .control
+                        .is_exhausted(shared.epoch.load(Ordering::Relaxed))
+                {
+                    budget_exhausted = true;
+                    shared.stop();
+                }
+                match stop_rx.recv_timeout(Duration::from_millis(5)) {
+                    Ok(()) => shared.stop(),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        thread::sleep(Duration::from_millis(5))
+                    }
+                }
+            }
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("A* worker panicked"))
+                .collect()
+        });
+
+        let stats = shared.stats(&self.control.reporter);
+        self.epoch = shared.epoch.load(Ordering::SeqCst);
+        self.n_successors = shared.n_successors.load(Ordering::SeqCst);
+        self.n_dead_ends = shared.n_dead_ends.load(Ordering::SeqCst);
+        // 지켜보는 쪽이 한도를 확인하기 전에 스레드들이 끝났을 수 있다.
+        let budget_exhausted = budget_exhausted || self.control.is_exhausted(self.epoch);
+        if budget_exhausted {
+            self.control.finish(stats, SolveOutcome::BudgetExhausted);
+            // 찾은 목표가 있으면 그 목표, 없으면 h가 가장 작았던 상태
+            let target = shared.goal.lock().unwrap().take().or_else(|| {
+                let best_state = shared.best_state.lock().unwrap().take();
+                best_state.map(|(_, state)| state)
+            });
+            return target.map(|key| Self::construct_path_from(key, &initial_state, g_scores));
+        }
+        if shared.stop.load(Ordering::SeqCst) {
+            self.control.finish(stats, SolveOutcome::Stopped);
+            return None;
+        }
+        let goal = shared.goal.lock().unwrap().take();
+        match goal {
+            Some(goal) => {
+                self.control.finish(stats, SolveOutcome::Found);
+                Some(Self::construct_path_from(goal, &initial_state, g_scores))
+            }
+            None => {
+                self.control.finish(stats, SolveOutcome::NoPath);
+                None
+            }
+        }
+    }
+
+    /// 한 스레드의 탐색. 자기가 맡은 상태의 g_score를 돌려준다.
+    fn work(shared: &Shared<H>, inbox: mpsc::Receiver<Mail>) -> GScore {
+        let mut open_set = BinaryHeap::new();
+        let mut g_score = GScore::new();
+        while !shared.stop.load(Ordering::SeqCst) {
+            while let Ok(mail) = inbox.try_recv() {
+                if let Some(message) = mail {
+                    Self::receive(shared, message, &mut g_score, &mut open_set);
+                }
+            }
+            let Some(current_node) = open_set.pop() else {
+                if shared.n_pending.load(Ordering::SeqCst) == 0 {
+                    break;
+                }
+                // 다른 스레드가 상태를 보내거나, 탐색이 끝나거나 멈출 때(`Shared::wake_all()`)까지 기다린다.
+                if let Ok(Some(message)) = inbox.recv() {
+                    Self::receive(shared, message, &mut g_score, &mut open_set);
+                }
+                continue;
+            };
+            // 더 작은 g로 다시 받은 상태
+            if current_node.g > g_score[&current_node.state].0 {
+                shared.done_one();
+                continue;
+            }
+            Self::expand(shared, current_node);
+        }
+        g_score
+    }
+
+    /// 받은 상태가 g를 줄이고 f가 찾은 목표의 g보다 작으면 open set에 넣는다.
+    fn receive(
+        shared: &Shared<H>,
+        (key, g, h, parent): Message,
+        g_score: &mut GScore,
+        open_set: &mut BinaryHeap<Node<StateKey>>,
+    ) {
+        if g + h < shared.best_g.load(Ordering::SeqCst)
+            && g < g_score.get(&key).map_or(u32::MAX, |&(g, _)| g)
+        {
+            if g_score.insert(key.clone(), (g, parent)).is_none() {
+                shared.n_visited.fetch_add(1, Ordering::Relaxed);
+            }
+            open_set.push(Node::new(key, g, h));
+        } else {
+            shared.done_one();
+        }
+    }
+
+    fn expand(shared: &Shared<H>, current_node: Node<StateKey>) {
+        let h = current_node.h();
+        if h < shared.best_h.fetch_min(h, Ordering::Relaxed) {
+            let mut best_state = shared.best_state.lock().unwrap();
+            if best_state.as_ref().is_none_or(|&(best_h, _)| h < best_h) {
+                *best_state = Some((h, current_node.state.clone()));
+            }
+        }
+        // 이 상태를 지나는 경로는 이미 찾은 목표보다 짧을 수 없다.
+        if current_node.f() >= shared.best_g.load(Ordering::SeqCst) {
+            shared.done_one();
+            return;
+        }
+        let current_key = current_node.state;
+        let current_state = State::from_key(&current_key, shared.config);
+        if current_state.is_search_goal() {
+            let mut goal = shared.goal.lock().unwrap();
+            if current_node.g < shared.best_g.load(Ordering::SeqCst) {
+                shared.best_g.store(current_node.g, Ordering::SeqCst);
+                *goal = Some(current_key);
+            }
+            shared.done_one();
+            return;
+        }
+
+        shared.epoch.fetch_add(1, Ordering::Relaxed);
+        let next_g = current_node.g + 1;
+        let mut n_successors = 0;
+        for mv in current_state.useful_moves() {
+            n_successors += 1;
+            let mut neighbor_state = current_state.clone();
+            let _ = neighbor_state.apply(mv);
+            let h = shared.heuristic.estimate(&neighbor_state);
+            shared.send((neighbor_state.key(), next_g, h, Some(current_key.clone())));
+        }
+        shared
+            .n_successors
+            .fetch_add(n_successors, Ordering::Relaxed);
+        if n_successors == 0 {
+            shared.n_dead_ends.fetch_add(1, Ordering::Relaxed);
+        }
+        // 자식을 모두 보낸 다음에 줄여야 n_pending이 잘못 0이 되지 않는다.
+        shared.done_one();
+    }
+
+    /// 키에서 만든 상태는 비커의 순서가 다르므로 경로의 처음에는 initial_state를 쓴다.
+    fn construct_path_from(
+        goal: StateKey,
+        initial_state: &State,
+        g_scores: Vec<GScore>,
+    ) -> Vec<State> {
+        let config = initial_state.config();
+        let came_from: GScore = g_scores.into_iter().flatten().collect();
+        let mut path = vec![State::from_key(&goal, config)];
+        let mut key = goal;
+        while let Some((_, Some(parent))) = came_from.get(&key) {
+            key = parent.clone();
+            path.push(State::from_key(&key, config));
+        }
+        path.reverse();
+        path[0] = initial_state.clone();
+        path
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fmt,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/// 탐색하는 동안 이만큼마다 SolveStats를 보낸다.
+pub const REPORT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 탐색 통계
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    /// 펼친(expand) 상태의 개수
+    pub expanded: usize,
+    /// 아직 펼치지 않은 상태의 개수. IdaStar에서는 지금 경로의 길이이다.
+    pub frontier: usize,
+    /// 한 번이라도 만난 상태의 개수. IdaStar에서는 기억하고 있는 상태의 개수이다.
+    pub visited: usize,
+    /// 펼친 상태의 h 중 가장 작은 값
+    pub best_h: u32,
+    pub elapsed: Duration,
+    pub outcome: SolveOutcome,
+}
+
+impl fmt::Display for SolveStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.outcome != SolveOutcome::Searching {
+            write!(f, "{}, ", self.outcome)?;
+        }
+        write!(
+            f,
+            "{} expanded, {} open, {} seen, h {}, {:.1}s",
+            self.expanded,
+            self.frontier,
+            self.visited,
+            self.best_h,
+            self.elapsed.as_secs_f32()
+        )
+    }
+}
+
+/// find_path()가 어떻게 끝났는지
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SolveOutcome {
+    /// 아직 끝나지 않았다. 탐색하는 동안 보내는 SolveStats는 언제나 이 값이다.
+    #[default]
+    Searching,
+    Found,
+    /// 목표까지 가는 경로가 없다.
+    NoPath,
+    /// 멈추라는 신호를 받았다.
+    Stopped,
+    /// 한도에 닿았다. See `SolverStrategy::budget_exhausted()`.
+    BudgetExhausted,
+}
+
+impl fmt::Display for SolveOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Searching => "Searching",
+            Self::Found => "Found",
+            Self::NoPath => "No path",
+            Self::Stopped => "Stopped",
+            Self::BudgetExhausted => "Budget exhausted",
+        };
+        write!(f, "{text}")
+    }
+}
+
+/// 탐색하는 동안 SolveStats를 REPORT_INTERVAL마다 보낸다.
+#[derive(Debug, Default)]
+pub(crate) struct Reporter {
+    sender: Option<mpsc::Sender<SolveStats>>,
+    start: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl Reporter {
+    pub(crate) fn set_sender(&mut self, sender: mpsc::Sender<SolveStats>) {
+        self.sender = Some(sender);
+    }
+
+    /// find_path()를 시작할 때 부른다.
+    pub(crate) fn start(&mut self) {
+        let now = Instant::now();
+        self.start = Some(now);
+        self.last = Some(now);
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.start.map_or(Duration::ZERO, |start| start.elapsed())
+    }
+
+    /// 보낼 곳이 있고 지난번에 보낸 지 REPORT_INTERVAL이 지났으면 true
+    pub(crate) fn is_due(&mut self) -> bool {
+        if self.sender.is_none() {
+            return false;
+        }
+        let now = Instant::now();
+        match self.last {
+            Some(last) if now - last < REPORT_INTERVAL => false,
+            _ => {
+                self.last = Some(now);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn send(&self, stats: SolveStats) {
+        if let Some(sender) = &self.sender {
+            // 받는 쪽이 이미 다른 탐색으로 넘어갔을 수 있다.
+            let _ = sender.send(stats);
+        }
+    }
+}
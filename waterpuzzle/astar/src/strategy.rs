@@ -0,0 +1,193 @@
+use std::{fmt, sync::mpsc};
+
+use state::state::State;
+
+use crate::{
+    budget::SolveBudget,
+    exaustive_astar::ExaustiveAStar,
+    fast_astar::FastAStar,
+    heuristic::{Heuristic, MinimumPours},
+    ida_star::IdaStar,
+    parallel_astar::ParallelAStar,
+    stats::{Reporter, SolveOutcome, SolveStats},
+};
+
+/// 처음 상태에서 목표까지의 경로를 찾는 방법
+pub trait SolverStrategy {
+    /// 찾은 경로는 initial_state부터 목표까지의 상태들이다. 찾지 못했거나 멈추라는 신호를 받으면 None
+    fn find_path(
+        &mut self,
+        initial_state: State,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Option<Vec<State>>;
+
+    /// 아래의 메서드들이 쓰는 한도와 통계. See `SolveControl`.
+    fn control(&self) -> &SolveControl;
+
+    fn control_mut(&mut self) -> &mut SolveControl;
+
+    /// find_path()가 도는 동안 REPORT_INTERVAL마다 SolveStats를 보낼 곳
+    fn report_to(&mut self, sender: mpsc::Sender<SolveStats>) {
+        self.control_mut().reporter.set_sender(sender);
+    }
+
+    /// 마지막 find_path()의 통계와 SolveOutcome
+    fn stats(&self) -> SolveStats {
+        self.control().stats
+    }
+
+    /// 다음 find_path()부터 쓴다.
+    fn set_budget(&mut self, budget: SolveBudget) {
+        self.control_mut().budget = budget;
+    }
+
+    /// 마지막 find_path()가 한도에 닿아 멈췄으면 true. 그때 돌려준 경로는 가장 짧지 않거나 목표까지 가지 않을 수 있다.
+    fn budget_exhausted(&self) -> bool {
+        self.stats().outcome == SolveOutcome::BudgetExhausted
+    }
+}
+
+/// 모든 솔버가 같게 쓰는 한도, SolveStats를 보낼 곳, 끝난 탐색의 통계.
+/// 솔버는 하나씩 가지고 있다가 control()로 내어 주고, SolverStrategy의 나머지 메서드가 이것을 쓴다.
+#[derive(Debug, Default)]
+pub struct SolveControl {
+    pub(crate) budget: SolveBudget,
+    pub(crate) reporter: Reporter,
+    // 끝난 탐색의 통계. 탐색하는 동안 보내는 SolveStats는 솔버가 그때그때 만든다.
+    pub(crate) stats: SolveStats,
+}
+
+impl SolveControl {
+    /// find_path()를 시작할 때 부른다.
+    pub(crate) fn start(&mut self) {
+        self.reporter.start();
+        self.stats = SolveStats::default();
+    }
+
+    /// expanded개를 펼쳤을 때 한도에 닿았으면 true
+    pub(crate) fn is_exhausted(&self, expanded: usize) -> bool {
+        self.budget.is_exhausted(expanded, self.reporter.elapsed())
+    }
+
+    /// find_path()가 끝날 때 그때의 통계와 함께 부른다.
+    pub(crate) fn finish(&mut self, stats: SolveStats, outcome: SolveOutcome) {
+        self.stats = SolveStats { outcome, ..stats };
+    }
+}
+
+impl<H: Heuristic> SolverStrategy for FastAStar<H> {
+    fn find_path(
+        &mut self,
+        initial_state: State,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Option<Vec<State>> {
+        FastAStar::find_path(self, initial_state, stop_rx)
+    }
+
+    fn control(&self) -> &SolveControl {
+        &self.control
+    }
+
+    fn control_mut(&mut self) -> &mut SolveControl {
+        &mut self.control
+    }
+}
+
+impl<H: Heuristic> SolverStrategy for ExaustiveAStar<H> {
+    fn find_path(
+        &mut self,
+        initial_state: State,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Option<Vec<State>> {
+        ExaustiveAStar::find_path(self, initial_state, stop_rx)
+    }
+
+    fn control(&self) -> &SolveControl {
+        &self.control
+    }
+
+    fn control_mut(&mut self) -> &mut SolveControl {
+        &mut self.control
+    }
+}
+
+impl<H: Heuristic + Sync> SolverStrategy for ParallelAStar<H> {
+    fn find_path(
+        &mut self,
+        initial_state: State,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Option<Vec<State>> {
+        ParallelAStar::find_path(self, initial_state, stop_rx)
+    }
+
+    fn control(&self) -> &SolveControl {
+        &self.control
+    }
+
+    fn control_mut(&mut self) -> &mut SolveControl {
+        &mut self.control
+    }
+}
+
+impl<H: Heuristic> SolverStrategy for IdaStar<H> {
+    fn find_path(
+        &mut self,
+        initial_state: State,
+        stop_rx: mpsc::Receiver<()>,
+    ) -> Option<Vec<State>> {
+        IdaStar::find_path(self, initial_state, stop_rx)
+    }
+
+    fn control(&self) -> &SolveControl {
+        &self.control
+    }
+
+    fn control_mut(&mut self) -> &mut SolveControl {
+        &mut self.control
+    }
+}
+
+/// 실행 중에 고를 수 있는 SolverStrategy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// 처음 찾은 경로. 빠르지만 가장 짧지 않을 수 있다. See `FastAStar`.
+    Fast,
+    /// 가장 짧은 경로. 찾은 목표보다 짧은 경로가 나올 수 없을 때까지 살펴본다. See `ExaustiveAStar`.
+    #[default]
+    Exhaustive,
+    /// Exhaustive와 같은 길이의 경로를 모든 코어를 써서 찾는다. See `ParallelAStar`.
+    Parallel,
+    /// 가장 짧은 경로를 찾으면서 메모리를 탐색 깊이만큼만 쓴다. 큰 판에 쓴다. See `IdaStar`.
+    Bounded,
+}
+
+impl Strategy {
+    pub const ALL: [Self; 4] = [Self::Fast, Self::Exhaustive, Self::Parallel, Self::Bounded];
+
+    /// Fast만 빠른 BoundaryCount를 쓰고, 나머지는 가장 짧은 경로를 찾도록 admissible한 MinimumPours를 쓴다.
+    pub fn solver(self) -> Box<dyn SolverStrategy> {
+        match self {
+            Self::Fast => Box::new(FastAStar::new()),
+            Self::Exhaustive => Box::new(ExaustiveAStar::with_heuristic(MinimumPours)),
+            Self::Parallel => Box::new(ParallelAStar::with_heuristic(MinimumPours)),
+            Self::Bounded => Box::new(IdaStar::with_heuristic(MinimumPours)),
+        }
+    }
+
+    /// 한도에 닿지 않고 찾은 경로가 언제나 가장 짧으면 true
+    pub fn finds_shortest(self) -> bool {
+        self != Self::Fast
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Fast => "Fast hint",
+            Self::Exhaustive => "Optimal hint",
+            Self::Parallel => "Parallel optimal hint",
+            Self::Bounded => "Low memory optimal hint",
+        };
+        write!(f, "{name}")
+    }
+}
@@ -0,0 +1,118 @@
+//! 한 스레드의 A*가 만난 상태들의 표
+
+use std::{
+    hash::{BuildHasher, RandomState},
+    mem,
+};
+
+use hashbrown::HashTable;
+use state::{config::PuzzleConfig, key::StateKey, state::State};
+
+/// 상태의 번호
+pub type StateId = u32;
+
+/// 만난 상태마다 번호를 붙이고 키, 지금까지 찾은 가장 작은 g, 그때의 부모를 적어 둔다.
+/// 키는 keys에 한 번만 저장하고 해시 표와 open set, 부모는 번호로 가리키므로 상태 하나에 드는 메모리가 작다.
+#[derive(Debug, Default)]
+pub struct Visited {
+    // 번호만 넣는 해시 표. 키는 keys[번호]에서 찾아 비교한다.
+    ids: HashTable<StateId>,
+    hasher: RandomState,
+    keys: Vec<StateKey>,
+    g_scores: Vec<u32>,
+    // 처음 상태의 부모는 자기 자신이다.
+    parents: Vec<StateId>,
+}
+
+impl Visited {
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// 표가 잡아 둔 메모리를 상태 하나마다 나눈 바이트 수. 큰 판의 Cord 키가 따로 잡는 메모리도 센다.
+    pub fn bytes_per_state(&self) -> usize {
+        let key_bytes: usize = self
+            .keys
+            .iter()
+            .map(|key| match key {
+                StateKey::Packed(_) => 0,
+                StateKey::Cord(cord) => mem::size_of_val(&**cord),
+            })
+            .sum();
+        let bytes = self.ids.allocation_size()
+            + self.keys.capacity() * mem::size_of::<StateKey>()
+            + key_bytes
+            + self.g_scores.capacity() * mem::size_of::<u32>()
+            + self.parents.capacity() * mem::size_of::<StateId>();
+        bytes / self.len().max(1)
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.keys.clear();
+        self.g_scores.clear();
+        self.parents.clear();
+    }
+
+    /// 처음 상태를 넣고 그 번호를 돌려준다.
+    pub fn insert_initial(&mut self, key: StateKey) -> StateId {
+        self.clear();
+        self.push(key, 0, 0)
+    }
+
+    /// parent에서 한 번 부어 g로 key에 닿았다. 처음 만났거나 g가 줄었으면 g와 부모를 바꾸고 번호를 돌려준다.
+    pub fn relax(&mut self, key: StateKey, g: u32, parent: StateId) -> Option<StateId> {
+        let hash = self.hasher.hash_one(&key);
+        let keys = &self.keys;
+        match self.ids.find(hash, |&id| keys[id as usize] == key) {
+            Some(&id) if g < self.g_scores[id as usize] => {
+                self.g_scores[id as usize] = g;
+                self.parents[id as usize] = parent;
+                Some(id)
+            }
+            Some(_) => None,
+            None => Some(self.push_hashed(hash, key, g, parent)),
+        }
+    }
+
+    fn push(&mut self, key: StateKey, g: u32, parent: StateId) -> StateId {
+        let hash = self.hasher.hash_one(&key);
+        self.push_hashed(hash, key, g, parent)
+    }
+
+    fn push_hashed(&mut self, hash: u64, key: StateKey, g: u32, parent: StateId) -> StateId {
+        let id = self.keys.len() as StateId;
+        let (keys, hasher) = (&self.keys, &self.hasher);
+        self.ids
+            .insert_unique(hash, id, |&id| hasher.hash_one(&keys[id as usize]));
+        self.keys.push(key);
+        self.g_scores.push(g);
+        self.parents.push(parent);
+        id
+    }
+
+    pub fn g(&self, id: StateId) -> u32 {
+        self.g_scores[id as usize]
+    }
+
+    pub fn state(&self, id: StateId, config: PuzzleConfig) -> State {
+        State::from_key(&self.keys[id as usize], config)
+    }
+
+    /// 처음 상태에서 id까지의 경로. 키에서 만든 상태는 비커의 순서가 다르므로 처음에는 initial_state를 쓴다.
+    pub fn path_to(&self, mut id: StateId, initial_state: &State) -> Vec<State> {
+        let config = initial_state.config();
+        let mut path = vec![];
+        while self.parents[id as usize] != id {
+            path.push(self.state(id, config));
+            id = self.parents[id as usize];
+        }
+        path.push(initial_state.clone());
+        path.reverse();
+        path
+    }
+}
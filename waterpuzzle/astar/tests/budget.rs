@@ -0,0 +1,54 @@
+//! 한도(SolveBudget)에 닿은 솔버가 찾은 도중의 경로를 돌려주는지 확인한다.
+
+use std::{sync::mpsc, time::Duration};
+
+use astar::{budget::SolveBudget, strategy::Strategy};
+use state::{config::PuzzleConfig, moves::moves_of_path, state::State};
+
+fn generate(n_colors: usize, seed: u64) -> State {
+    let mut state = State::new(PuzzleConfig::new(n_colors, 2, 4));
+    state.generate_with_seed(seed);
+    state
+}
+
+#[test]
+fn returns_partial_path_when_nodes_run_out() {
+    let state = generate(11, 0);
+    for strategy in Strategy::ALL {
+        let mut solver = strategy.solver();
+        solver.set_budget(SolveBudget::UNLIMITED.with_max_nodes(20));
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        let path = solver.find_path(state.clone(), stop_rx);
+
+        assert!(solver.budget_exhausted(), "{strategy}");
+        let path = path.unwrap_or_else(|| panic!("{strategy}: no partial path"));
+        assert_eq!(path[0], state);
+        assert!(moves_of_path(&path).is_some(), "{strategy}");
+        let last = path.last().unwrap();
+        assert!(last.is_search_goal() || last.h < state.h, "{strategy}");
+    }
+}
+
+#[test]
+fn stops_when_time_runs_out() {
+    let state = generate(12, 0);
+    for strategy in Strategy::ALL {
+        let mut solver = strategy.solver();
+        solver.set_budget(SolveBudget::UNLIMITED.with_max_time(Duration::ZERO));
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        solver.find_path(state.clone(), stop_rx);
+        assert!(solver.budget_exhausted(), "{strategy}");
+    }
+}
+
+#[test]
+fn unlimited_budget_is_not_exhausted() {
+    let state = generate(5, 0);
+    for strategy in Strategy::ALL {
+        let mut solver = strategy.solver();
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        let path = solver.find_path(state.clone(), stop_rx);
+        assert!(!solver.budget_exhausted(), "{strategy}");
+        assert!(path.unwrap().last().unwrap().is_search_goal(), "{strategy}");
+    }
+}
@@ -0,0 +1,107 @@
+//! 여러 테스트가 함께 쓰는, 작은 판의 모든 상태와 목표까지의 실제 거리
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use state::{config::PourRule, notation::BLANK, state::State};
+
+/// (색의 개수, 빈 비커의 개수, 용량)
+pub const SMALL_BOARDS: [(usize, usize, usize); 6] = [
+    (2, 1, 2),
+    (2, 1, 3),
+    (2, 2, 2),
+    (3, 1, 2),
+    (3, 1, 3),
+    (2, 1, 4),
+];
+
+/// 색마다 capacity개의 물을 n_colors + n_empty개의 비커에 나누어 담는 모든 판
+pub fn enumerate_states(
+    n_colors: usize,
+    n_empty: usize,
+    capacity: usize,
+    rule: PourRule,
+) -> HashSet<State> {
+    fn fill(
+        beakers: &mut Vec<String>,
+        remaining: &mut [usize],
+        n_beakers: usize,
+        capacity: usize,
+        out: &mut Vec<String>,
+    ) {
+        let current = beakers.last().unwrap();
+        if remaining.iter().all(|&count| count == 0) {
+            if beakers.len() <= n_beakers {
+                let mut tokens: Vec<String> = beakers
+                    .iter()
+                    .map(|beaker| format!("{beaker:.<capacity$}"))
+                    .collect();
+                tokens.resize(n_beakers, BLANK.to_string().repeat(capacity));
+                out.push(tokens.join("|"));
+            }
+            return;
+        }
+        if current.len() < capacity {
+            for color in 0..remaining.len() {
+                if remaining[color] > 0 {
+                    remaining[color] -= 1;
+                    beakers.last_mut().unwrap().push(b"RBYGMT"[color] as char);
+                    fill(beakers, remaining, n_beakers, capacity, out);
+                    beakers.last_mut().unwrap().pop();
+                    remaining[color] += 1;
+                }
+            }
+        }
+        if beakers.len() < n_beakers {
+            beakers.push(String::new());
+            fill(beakers, remaining, n_beakers, capacity, out);
+            beakers.pop();
+        }
+    }
+
+    let mut boards = Vec::new();
+    fill(
+        &mut vec![String::new()],
+        &mut vec![capacity; n_colors],
+        n_colors + n_empty,
+        capacity,
+        &mut boards,
+    );
+    boards
+        .iter()
+        .map(|board| {
+            let state: State = board.parse().unwrap();
+            State::from_beakers(state.beakers.clone(), state.config().with_pour_rule(rule))
+        })
+        .collect()
+}
+
+/// 목표에 닿을 수 있는 상태마다 목표까지의 가장 짧은 붓기 수
+pub fn distances(states: &HashSet<State>) -> HashMap<State, u32> {
+    let mut predecessors: HashMap<State, Vec<State>> = HashMap::new();
+    for state in states {
+        for i in 0..state.n_beakers() {
+            for j in 0..state.n_beakers() {
+                let mut next = state.clone();
+                if next.pour(i, j).is_ok() {
+                    predecessors.entry(next).or_default().push(state.clone());
+                }
+            }
+        }
+    }
+    let mut distance = HashMap::new();
+    let mut queue = VecDeque::new();
+    for state in states.iter().filter(|state| state.is_search_goal()) {
+        distance.insert(state.clone(), 0);
+        queue.push_back(state.clone());
+    }
+    while let Some(state) = queue.pop_front() {
+        let d = distance[&state];
+        for prev in predecessors.get(&state).into_iter().flatten() {
+            if !distance.contains_key(prev) {
+                distance.insert(prev.clone(), d + 1);
+                queue.push_back(prev.clone());
+            }
+        }
+    }
+    distance
+}
@@ -0,0 +1,57 @@
+//! 작은 판의 상태를 모두 만들어 목표까지의 실제 거리를 구하고, 각 Heuristic이 그보다 크게 추정하지 않는지 확인한다.
+
+mod common;
+
+use astar::heuristic::{BoundaryCount, ColorFragmentation, Heuristic, MinimumPours};
+use common::{SMALL_BOARDS, distances, enumerate_states};
+use state::{config::PourRule, state::State};
+
+fn assert_admissible<H: Heuristic>(heuristic: H) {
+    for (n_colors, n_empty, capacity) in SMALL_BOARDS {
+        for rule in PourRule::ALL {
+            let states = enumerate_states(n_colors, n_empty, capacity, rule);
+            let distance = distances(&states);
+            assert!(!distance.is_empty());
+            for (state, d) in distance {
+                let h = heuristic.estimate(&state);
+                assert!(h <= d, "{state} ({rule}): estimate {h} > distance {d}");
+            }
+        }
+    }
+}
+
+#[test]
+fn color_fragmentation_is_admissible() {
+    assert_admissible(ColorFragmentation);
+}
+
+#[test]
+fn minimum_pours_is_admissible() {
+    assert_admissible(MinimumPours);
+}
+
+#[test]
+fn minimum_pours_is_not_weaker_than_color_fragmentation() {
+    for (n_colors, n_empty, capacity) in SMALL_BOARDS {
+        for state in enumerate_states(n_colors, n_empty, capacity, PourRule::default()) {
+            assert!(MinimumPours.estimate(&state) >= ColorFragmentation.estimate(&state));
+        }
+    }
+}
+
+#[test]
+fn boundary_count_can_overestimate() {
+    // 한 번 부으면 완성되지만 빈 층과의 경계 두 개가 함께 없어진다.
+    let state: State = "B.|B.|RR".parse().unwrap();
+    let distance = distances(&enumerate_states(2, 1, 2, PourRule::default()));
+    assert_eq!(distance[&state], 1);
+    assert_eq!(BoundaryCount.estimate(&state), 2);
+}
+
+#[test]
+fn goal_estimates_zero() {
+    let state: State = "RRR|BBB|...".parse().unwrap();
+    assert_eq!(BoundaryCount.estimate(&state), 0);
+    assert_eq!(ColorFragmentation.estimate(&state), 0);
+    assert_eq!(MinimumPours.estimate(&state), 0);
+}
@@ -0,0 +1,83 @@
+//! 작은 판의 모든 상태에서 IdaStar가 ExaustiveAStar와 같이 가장 짧은 경로를 찾는지 실제 거리와 비교해 확인하고,
+//! 펼친 상태의 표가 탐색 깊이에 비례하는 크기를 넘지 않는지 확인한다.
+
+mod common;
+
+use std::sync::mpsc;
+
+use astar::{
+    exaustive_astar::ExaustiveAStar,
+    heuristic::{Heuristic, MinimumPours},
+    ida_star::IdaStar,
+    strategy::SolverStrategy,
+};
+use common::{SMALL_BOARDS, distances, enumerate_states};
+use state::{
+    config::{PourRule, PuzzleConfig},
+    moves::moves_of_path,
+    state::State,
+};
+
+/// 찾은 경로의 붓기 수. 경로가 처음 상태에서 목표까지 붓기로 이어지는지도 확인한다.
+fn path_length(path: Option<Vec<State>>, state: &State) -> Option<u32> {
+    let path = path?;
+    assert_eq!(path[0], *state);
+    assert!(path.last().unwrap().is_search_goal(), "{state}");
+    assert!(moves_of_path(&path).is_some(), "{state}");
+    Some(path.len() as u32 - 1)
+}
+
+fn ida_star<H: Heuristic>(solver: &mut IdaStar<H>, state: &State) -> Option<u32> {
+    let (_stop_tx, stop_rx) = mpsc::channel();
+    path_length(solver.find_path(state.clone(), stop_rx), state)
+}
+
+fn exaustive_astar<H: Heuristic>(solver: &mut ExaustiveAStar<H>, state: &State) -> Option<u32> {
+    let (_stop_tx, stop_rx) = mpsc::channel();
+    path_length(solver.find_path(state.clone(), stop_rx), state)
+}
+
+#[test]
+fn ida_star_finds_shortest_paths() {
+    // 한 솔버로 여러 번 찾는 것도 함께 확인한다.
+    let mut ida = IdaStar::with_heuristic(MinimumPours);
+    // 표는 다시 펼치지 않게 할 뿐이므로 꺼도 같은 경로를 찾는다.
+    let mut ida_without_table = IdaStar::with_heuristic(MinimumPours);
+    ida_without_table.table_entries_per_depth = 0;
+    let mut astar = ExaustiveAStar::with_heuristic(MinimumPours);
+    let mut n_solved = 0;
+    for (n_colors, n_empty, capacity) in SMALL_BOARDS {
+        for rule in PourRule::ALL {
+            let states = enumerate_states(n_colors, n_empty, capacity, rule);
+            let distance = distances(&states);
+            for state in &states {
+                let d = distance.get(state).copied();
+                assert_eq!(ida_star(&mut ida, state), d, "{state} ({rule})");
+                assert_eq!(
+                    ida_star(&mut ida_without_table, state),
+                    d,
+                    "{state} ({rule})"
+                );
+                assert_eq!(exaustive_astar(&mut astar, state), d, "{state} ({rule})");
+                n_solved += d.is_some() as usize;
+            }
+        }
+    }
+    assert!(n_solved > 500);
+}
+
+#[test]
+fn table_grows_with_depth_only() {
+    let mut solver = IdaStar::with_heuristic(MinimumPours);
+    solver.table_entries_per_depth = 16;
+    for seed in 0..4 {
+        let mut state = State::new(PuzzleConfig::new(6, 2, 4));
+        state.generate_with_seed(seed);
+        let length = ida_star(&mut solver, &state).unwrap();
+        // 마지막 반복의 f의 한계는 가장 짧은 경로의 길이이다.
+        assert!(
+            solver.stats().visited <= 16 * length as usize,
+            "seed {seed}"
+        );
+    }
+}
@@ -0,0 +1,49 @@
+//! ParallelAStar가 ExaustiveAStar와 같은 길이의 경로를 찾는지 확인한다.
+
+use std::sync::mpsc;
+
+use astar::{
+    exaustive_astar::ExaustiveAStar, heuristic::MinimumPours, parallel_astar::ParallelAStar,
+    stats::SolveOutcome, strategy::SolverStrategy,
+};
+use state::{config::PuzzleConfig, moves::moves_of_path, state::State};
+
+#[test]
+fn same_length_as_exaustive_astar() {
+    for seed in 0..8 {
+        let mut state = State::new(PuzzleConfig::new(5, 2, 4));
+        state.generate_with_seed(seed);
+
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        // 가장 짧은 경로는 admissible한 추정으로만 보장된다.
+        let expected =
+            ExaustiveAStar::with_heuristic(MinimumPours).find_path(state.clone(), stop_rx);
+
+        let mut parallel = ParallelAStar::with_heuristic(MinimumPours);
+        parallel.n_threads = 4;
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        let path = parallel.find_path(state.clone(), stop_rx);
+
+        assert_eq!(
+            path.as_ref().map(Vec::len),
+            expected.as_ref().map(Vec::len),
+            "seed {seed}"
+        );
+        if let Some(path) = path {
+            assert!(moves_of_path(&path).is_some());
+            assert!(path.last().unwrap().is_completed());
+        }
+    }
+}
+
+#[test]
+fn stops_on_signal() {
+    let mut state = State::new(PuzzleConfig::new(12, 2, 4));
+    state.generate_with_seed(0);
+    let (stop_tx, stop_rx) = mpsc::channel();
+    stop_tx.send(()).unwrap();
+    let mut parallel = ParallelAStar::new();
+    parallel.n_threads = 2;
+    assert!(parallel.find_path(state, stop_rx).is_none());
+    assert_eq!(parallel.stats().outcome, SolveOutcome::Stopped);
+}
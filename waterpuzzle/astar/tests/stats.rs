@@ -0,0 +1,72 @@
+//! 솔버가 탐색하는 동안 보내는 SolveStats와 끝난 뒤의 stats()가 맞는지 확인한다.
+
+use std::{sync::mpsc, time::Duration};
+
+use astar::{
+    budget::SolveBudget,
+    stats::{REPORT_INTERVAL, SolveOutcome},
+    strategy::Strategy,
+};
+use state::{config::PuzzleConfig, state::State};
+
+fn generate(n_colors: usize, seed: u64) -> State {
+    let mut state = State::new(PuzzleConfig::new(n_colors, 2, 4));
+    state.generate_with_seed(seed);
+    state
+}
+
+#[test]
+fn reports_grow_up_to_final_stats() {
+    let state = generate(30, 0);
+    for strategy in Strategy::ALL {
+        let mut solver = strategy.solver();
+        solver.set_budget(SolveBudget::UNLIMITED.with_max_time(REPORT_INTERVAL * 3));
+        let (stats_tx, stats_rx) = mpsc::channel();
+        solver.report_to(stats_tx);
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        solver.find_path(state.clone(), stop_rx);
+        let reports: Vec<_> = stats_rx.try_iter().collect();
+        let last = solver.stats();
+
+        assert!(solver.budget_exhausted(), "{strategy}");
+        assert!(!reports.is_empty(), "{strategy}: no report");
+        for pair in reports.windows(2) {
+            assert!(pair[0].expanded <= pair[1].expanded, "{strategy}");
+            assert!(pair[0].best_h >= pair[1].best_h, "{strategy}");
+            assert!(pair[0].elapsed <= pair[1].elapsed, "{strategy}");
+        }
+        let report = reports.last().unwrap();
+        assert!(report.expanded <= last.expanded, "{strategy}");
+        assert!(report.best_h >= last.best_h, "{strategy}");
+        assert!(report.elapsed <= last.elapsed, "{strategy}");
+        assert!(last.elapsed >= REPORT_INTERVAL * 3, "{strategy}");
+        // 탐색하는 동안에는 어떻게 끝날지 모른다.
+        assert!(
+            reports
+                .iter()
+                .all(|report| report.outcome == SolveOutcome::Searching),
+            "{strategy}"
+        );
+        assert_eq!(last.outcome, SolveOutcome::BudgetExhausted, "{strategy}");
+        assert!(
+            last.to_string().starts_with("Budget exhausted, "),
+            "{strategy}: {last}"
+        );
+    }
+}
+
+#[test]
+fn final_stats_count_the_node_budget() {
+    let state = generate(11, 0);
+    for strategy in Strategy::ALL {
+        let mut solver = strategy.solver();
+        solver.set_budget(SolveBudget::UNLIMITED.with_max_nodes(20));
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        solver.find_path(state.clone(), stop_rx);
+
+        assert!(solver.budget_exhausted(), "{strategy}");
+        let stats = solver.stats();
+        assert!(stats.expanded >= 20, "{strategy}: {stats}");
+        assert!(stats.elapsed < Duration::from_secs(10), "{strategy}: {stats}");
+    }
+}
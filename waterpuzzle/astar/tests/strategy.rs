@@ -0,0 +1,84 @@
+//! 가장 짧은 경로를 찾는다는 Strategy들이 같은 길이의 경로를 찾는지, 멈추라는 신호에 멈추는지 확인한다.
+
+use std::sync::mpsc;
+
+use astar::{stats::SolveOutcome, strategy::Strategy};
+use state::{config::PuzzleConfig, moves::moves_of_path, state::State};
+
+fn path_length(strategy: Strategy, state: &State) -> usize {
+    let mut solver = strategy.solver();
+    let (_stop_tx, stop_rx) = mpsc::channel();
+    let path = solver.find_path(state.clone(), stop_rx).unwrap();
+    assert_eq!(solver.stats().outcome, SolveOutcome::Found, "{strategy}");
+    assert!(moves_of_path(&path).is_some(), "{strategy}");
+    path.len() - 1
+}
+
+#[test]
+fn shortest_strategies_agree() {
+    for seed in 0..8 {
+        let mut state = State::new(PuzzleConfig::new(5, 2, 4));
+        state.generate_with_seed(seed);
+        let shortest = path_length(Strategy::Exhaustive, &state);
+        for strategy in Strategy::ALL {
+            let length = path_length(strategy, &state);
+            if strategy.finds_shortest() {
+                assert_eq!(length, shortest, "{strategy}, seed {seed}");
+            } else {
+                assert!(length >= shortest, "{strategy}, seed {seed}");
+            }
+        }
+    }
+}
+
+#[test]
+fn find_path_twice_on_one_solver() {
+    let boards: Vec<State> = [(6, 3), (4, 5)]
+        .into_iter()
+        .map(|(n_colors, seed)| {
+            let mut state = State::new(PuzzleConfig::new(n_colors, 2, 4));
+            state.generate_with_seed(seed);
+            state
+        })
+        .collect();
+    for strategy in Strategy::ALL {
+        let mut solver = strategy.solver();
+        // 멈추라는 신호를 받아 도중에 끝난 탐색
+        let (stop_tx, stop_rx) = mpsc::channel();
+        stop_tx.send(()).unwrap();
+        assert!(solver.find_path(boards[0].clone(), stop_rx).is_none());
+
+        for state in &boards {
+            let (_stop_tx, stop_rx) = mpsc::channel();
+            let path = solver.find_path(state.clone(), stop_rx).unwrap();
+            assert_eq!(path[0], *state, "{strategy}");
+            assert!(path.last().unwrap().is_search_goal(), "{strategy}");
+            assert_eq!(path.len() - 1, path_length(strategy, state), "{strategy}");
+            if strategy != Strategy::Parallel {
+                // 새 솔버와 같은 만큼 펼친다.
+                let mut fresh = strategy.solver();
+                let (_stop_tx, stop_rx) = mpsc::channel();
+                fresh.find_path(state.clone(), stop_rx);
+                assert_eq!(
+                    solver.stats().expanded,
+                    fresh.stats().expanded,
+                    "{strategy}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn every_strategy_stops_on_signal() {
+    let mut state = State::new(PuzzleConfig::new(12, 2, 4));
+    state.generate_with_seed(0);
+    for strategy in Strategy::ALL {
+        let mut solver = strategy.solver();
+        let (stop_tx, stop_rx) = mpsc::channel();
+        stop_tx.send(()).unwrap();
+        assert_eq!(solver.find_path(state.clone(), stop_rx), None, "{strategy}");
+        assert_eq!(solver.stats().outcome, SolveOutcome::Stopped, "{strategy}");
+        assert!(!solver.budget_exhausted(), "{strategy}");
+    }
+}
@@ -0,0 +1,64 @@
+//! Visited가 상태마다 키를 한 번만 저장하고, 번호로 g와 부모를 맞게 찾는지 확인한다.
+
+use std::{collections::VecDeque, mem};
+
+use astar::visited::Visited;
+use state::{config::PuzzleConfig, key::StateKey, state::State};
+
+/// 처음 상태에서 너비 우선으로 n개의 상태를 넣은 표
+fn visit(initial: &State, n: usize) -> Visited {
+    let mut visited = Visited::default();
+    let mut queue = VecDeque::from([visited.insert_initial(initial.key())]);
+    while let Some(id) = queue.pop_front() {
+        let state = visited.state(id, initial.config());
+        for mv in state.useful_moves() {
+            let mut next = state.clone();
+            next.apply(mv).unwrap();
+            if let Some(next_id) = visited.relax(next.key(), visited.g(id) + 1, id) {
+                queue.push_back(next_id);
+            }
+            if visited.len() == n {
+                return visited;
+            }
+        }
+    }
+    visited
+}
+
+#[test]
+fn stores_each_key_once() {
+    let mut initial = State::new(PuzzleConfig::new(7, 2, 4));
+    initial.generate_with_seed(1);
+    // 벡터의 용량이 꼭 찬 크기
+    let visited = visit(&initial, 1 << 12);
+    assert_eq!(visited.len(), 1 << 12);
+
+    // 키 하나, g와 부모, 해시 표의 번호와 제어 바이트. 키를 두 번 저장하면 키 두 개보다 커진다.
+    let bytes = visited.bytes_per_state();
+    assert!(bytes < mem::size_of::<StateKey>() + 24, "{bytes}");
+}
+
+#[test]
+fn relax_keeps_the_smallest_g() {
+    let mut initial = State::new(PuzzleConfig::new(4, 2, 4));
+    initial.generate_with_seed(5);
+    let visited = visit(&initial, 200);
+    assert_eq!(visited.len(), 200);
+    for id in 0..visited.len() as u32 {
+        let path = visited.path_to(id, &initial);
+        assert_eq!(path.len() as u32 - 1, visited.g(id));
+        assert_eq!(*path.last().unwrap(), visited.state(id, initial.config()));
+    }
+
+    let mut visited = visited;
+    let initial_id = visited.insert_initial(initial.key());
+    assert_eq!((visited.len(), initial_id), (1, 0));
+    // 처음 상태로 되돌아가는 길은 더 길므로 받지 않는다.
+    assert_eq!(visited.relax(initial.key(), 3, 0), None);
+    let mut next = initial.clone();
+    next.apply(initial.useful_moves().next().unwrap()).unwrap();
+    assert_eq!(visited.relax(next.key(), 5, 0), Some(1));
+    assert_eq!(visited.relax(next.key(), 5, 0), None);
+    assert_eq!(visited.relax(next.key(), 1, 0), Some(1));
+    assert_eq!((visited.len(), visited.g(1)), (2, 1));
+}
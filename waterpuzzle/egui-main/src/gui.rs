@@ -1,19 +1,32 @@
 use std::{
     error::Error,
     fs::File,
-    io::{BufReader, BufWriter, Read, Write},
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 use eframe::egui::{
-    self, Button, CentralPanel, Color32, Grid, Key, PointerButton, RichText, Sense, TopBottomPanel,
+    self, Button, CentralPanel, Color32, ComboBox, Grid, Key, PointerButton, RichText, ScrollArea,
+    Sense, TextEdit, TopBottomPanel, Window, vec2,
 };
-use puzzle::puzzle::Puzzle;
+use puzzle::{
+    generator::{Difficulty, PendingLevel},
+    puzzle::Puzzle,
+    save_file::{self, LoadError},
+    slots::{AUTOSAVE_SLOT, SlotInfo, SlotManager, format_age},
+    solver::Strategy,
+    stats::{GameResult, STATS_FILE_NAME, Statistics},
+};
+use state::config::{PourRule, PuzzleConfig};
 
-use crate::viewport::{SPACE_X, Viewport};
+use crate::viewport::{SPACE_X, Viewport, draw_preview, preview_size};
 
 pub const WIDTH: f32 = 1024.0;
 pub const HEIGHT: f32 = 720.0;
+// 슬롯이 생기기 전에 쓰던 저장 파일. 자동 저장 슬롯이 없을 때만 읽는다.
 const FILE_NAME: &str = "save.wp";
+const SAVE_DIR: &str = "saves";
 
 pub struct PuzzleGui {
     puzzle: Puzzle,
@@ -23,19 +36,44 @@ pub struct PuzzleGui {
     button_width: f32,
     button_height: f32,
     info: String,
+    // 레벨 코드나 글자로 적은 판을 넣는 칸. 새 판이 만들어지면 그 판의 코드로 바뀐다.
+    level_code_input: String,
+    notice: String,
+    // 다음 "New game"을 "Mystery" 판으로 만들지
+    mystery: bool,
+    // 다음 "New game"에서 쓸 붓기 규칙
+    pour_rule: PourRule,
+    // 다음 "New game"의 어려움
+    difficulty: Difficulty,
+    // "New game"에서 찾고 있는 판. 찾는 동안 지금 판을 그대로 둔다.
+    pending_level: Option<PendingLevel>,
+    slots: SlotManager,
+    // Load/Save 창을 보이는지
+    show_slots: bool,
+    slot_name_input: String,
+    // Load/Save 창을 열거나 슬롯을 바꿀 때 다시 읽는다.
+    slot_list: Vec<SlotInfo>,
+    stats: Statistics,
+    // 방금 끝난 판. 요약 창을 닫으면 None
+    summary: Option<GameResult>,
 }
 
 impl PuzzleGui {
-    pub fn new(n_rows: usize, n_cols: usize) -> Self {
+    pub fn new(n_rows: usize, n_cols: usize, config: PuzzleConfig) -> Self {
+        assert!(config.n_beakers() <= n_rows * n_cols);
         Self::from_puzzle_and_viewport(
-            Puzzle::random_new(n_rows * n_cols),
-            Viewport::new(n_rows, n_cols),
+            Puzzle::random_new(config),
+            Viewport::new(n_rows, n_cols, config.capacity),
         )
     }
 
     pub fn from_puzzle_and_viewport(puzzle: Puzzle, viewport: Viewport) -> Self {
         let top_panel_height = 30.0;
         let bottom_panel_height = 60.0;
+        let (stats, notice) = match Statistics::load_from(&stats_path()) {
+            Ok(stats) => (stats, "".to_string()),
+            Err(e) => (Statistics::default(), format!("Stats: {e}")),
+        };
         Self {
             puzzle,
             viewport,
@@ -44,55 +82,333 @@ impl PuzzleGui {
             button_width: 60.0,
             button_height: 30.0,
             info: "".to_string(),
+            level_code_input: "".to_string(),
+            notice,
+            mystery: false,
+            pour_rule: PourRule::default(),
+            difficulty: Difficulty::default(),
+            pending_level: None,
+            slots: SlotManager::new(SAVE_DIR),
+            show_slots: false,
+            slot_name_input: "".to_string(),
+            slot_list: Vec::new(),
+            stats,
+            summary: None,
+        }
+        .with_level_code_input()
+    }
+
+    fn with_level_code_input(mut self) -> Self {
+        self.sync_level_code_input();
+        self
+    }
+
+    fn sync_level_code_input(&mut self) {
+        self.mystery = self.puzzle.config().mystery;
+        self.pour_rule = self.puzzle.config().pour_rule;
+        self.level_code_input = self
+            .puzzle
+            .level_code()
+            .map(|level_code| level_code.to_string())
+            .unwrap_or_default();
+    }
+
+    fn open_level_code(&mut self) {
+        match Puzzle::from_code(&self.level_code_input) {
+            Ok(puzzle) => {
+                let n_cols = self.viewport.n_cols();
+                let n_rows = puzzle.config().n_beakers().div_ceil(n_cols);
+                self.set_puzzle(puzzle, n_rows, n_cols);
+                self.notice.clear();
+            }
+            Err(e) => self.notice = e,
+        }
+    }
+
+    fn set_puzzle(&mut self, puzzle: Puzzle, n_rows: usize, n_cols: usize) {
+        // 다른 판을 열었으므로 찾던 판은 버린다.
+        self.pending_level = None;
+        self.give_up();
+        self.viewport = Viewport::new(n_rows, n_cols, puzzle.config().capacity);
+        self.puzzle = puzzle;
+        self.sync_level_code_input();
+    }
+
+    fn new_game(&mut self) {
+        let config = self
+            .puzzle
+            .config()
+            .with_mystery(self.mystery)
+            .with_pour_rule(self.pour_rule);
+        // 앞서 찾던 판은 버린다.
+        self.pending_level = PendingLevel::spawn(config, self.difficulty);
+        match &self.pending_level {
+            Some(pending) => {
+                self.notice = format!("{}: looking for a level...", pending.difficulty())
+            }
+            None => self.start_game(|puzzle| puzzle.reset_with(config)),
+        }
+    }
+
+    /// 찾던 판이 나왔으면 그 판으로 새로 시작한다.
+    fn check_pending_level(&mut self) {
+        let Some(pending) = self.pending_level.take() else {
+            return;
+        };
+        match pending.check() {
+            None => self.pending_level = Some(pending),
+            Some(Some(level)) => {
+                self.start_game(|puzzle| puzzle.reset_with_level_code(level.level_code))
+            }
+            Some(None) => {
+                self.start_game(|puzzle| puzzle.reset_with(pending.config()));
+                self.notice = format!("No {} level found", pending.difficulty());
+            }
         }
     }
+
+    fn start_game(&mut self, reset: impl FnOnce(&mut Puzzle)) {
+        self.give_up();
+        reset(&mut self.puzzle);
+        self.sync_level_code_input();
+        self.notice.clear();
+        self.summary = None;
+    }
+
+    /// 풀지 않고 넘어가는 판을 진 판으로 기록한다.
+    fn give_up(&mut self) {
+        if let Some(result) = self.puzzle.give_up() {
+            self.record_game(result);
+        }
+    }
+
+    /// 판을 다 풀었으면 기록하고 요약 창을 연다.
+    fn finish_game(&mut self) {
+        if let Some(result) = self.puzzle.finish_game() {
+            self.record_game(result);
+            self.summary = Some(result);
+        }
+    }
+
+    fn record_game(&mut self, result: GameResult) {
+        self.stats.record(result);
+        if let Err(e) = self.stats.save_to(&stats_path()) {
+            self.notice = format!("Stats: {e}");
+        }
+    }
+
+    /// 저장 파일이 없으면 새 판을 만든다. 파일을 읽지 못하면 새 판을 만들고 이유를 알린다.
     pub fn load_or_new() -> Self {
         match Self::load() {
             Ok((puzzle, view)) => Self::from_puzzle_and_viewport(puzzle, view),
-            Err(_) => Self::new(3, 5),
+            Err(e) => {
+                let mut gui = Self::new(3, 5, PuzzleConfig::default());
+                if !matches!(&e, LoadError::Io(e) if e.kind() == ErrorKind::NotFound) {
+                    gui.notice = format!("Load: {e}");
+                }
+                gui
+            }
         }
     }
 
+    /// 자동 저장 슬롯에 저장한다.
     pub fn save(&self) -> Result<(), Box<dyn Error>> {
-        let file = File::create(FILE_NAME)?;
-        let mut writer = BufWriter::new(file);
+        self.slots.save(
+            AUTOSAVE_SLOT,
+            self.viewport.n_rows(),
+            self.viewport.n_cols(),
+            &self.puzzle,
+        )
+    }
 
-        writer.write_all(&(self.viewport.n_rows() as u32).to_le_bytes())?;
-        writer.write_all(&(self.viewport.n_cols() as u32).to_le_bytes())?;
-        self.puzzle.save(&mut writer)?;
-        writer.flush()?;
-        Ok(())
+    /// 자동 저장 슬롯을 읽는다. 슬롯이 없으면 예전 저장 파일을 읽는다.
+    pub fn load() -> Result<(Puzzle, Viewport), LoadError> {
+        let (n_rows, n_cols, puzzle) = match SlotManager::new(SAVE_DIR).load(AUTOSAVE_SLOT) {
+            Err(LoadError::Io(e)) if e.kind() == ErrorKind::NotFound => {
+                let file = File::open(FILE_NAME)?;
+                save_file::load(&mut BufReader::new(file))?
+            }
+            loaded => loaded?,
+        };
+        let view = Viewport::new(n_rows, n_cols, puzzle.config().capacity);
+        Ok((puzzle, view))
     }
 
-    pub fn load() -> Result<(Puzzle, Viewport), Box<dyn Error>> {
-        let file = File::open(FILE_NAME)?;
-        let mut reader = BufReader::new(file);
+    fn autosave(&mut self) {
+        let (n_rows, n_cols) = (self.viewport.n_rows(), self.viewport.n_cols());
+        if let Err(e) = self.slots.autosave(n_rows, n_cols, &self.puzzle) {
+            self.notice = format!("Autosave: {e}");
+        }
+    }
 
-        let mut buf = [0; 4];
-        reader.read_exact(&mut buf)?;
-        let n_rows = u32::from_le_bytes(buf) as usize;
+    fn refresh_slot_list(&mut self) {
+        match self.slots.list() {
+            Ok(slot_list) => self.slot_list = slot_list,
+            Err(e) => self.notice = format!("{}: {e}", self.slots.dir().display()),
+        }
+    }
 
-        reader.read_exact(&mut buf)?;
-        let n_cols = u32::from_le_bytes(buf) as usize;
+    fn save_slot(&mut self) {
+        let name = self.slot_name_input.trim();
+        let (n_rows, n_cols) = (self.viewport.n_rows(), self.viewport.n_cols());
+        match self.slots.save(name, n_rows, n_cols, &self.puzzle) {
+            Ok(()) => self.notice.clear(),
+            Err(e) => self.notice = e.to_string(),
+        }
+        self.refresh_slot_list();
+    }
 
-        let mut puzzle = Puzzle::empty_new(n_rows * n_cols);
-        puzzle.load(&mut reader)?;
+    fn load_slot(&mut self, name: &str) {
+        match self.slots.load(name) {
+            Ok((n_rows, n_cols, puzzle)) => {
+                self.set_puzzle(puzzle, n_rows, n_cols);
+                self.slot_name_input = name.to_string();
+                self.show_slots = false;
+                self.notice.clear();
+            }
+            Err(e) => self.notice = format!("{name}: {e}"),
+        }
+    }
 
-        let view = Viewport::new(n_rows, n_cols);
-        Ok((puzzle, view))
+    fn delete_slot(&mut self, name: &str) {
+        if let Err(e) = self.slots.delete(name) {
+            self.notice = format!("{name}: {e}");
+        }
+        self.refresh_slot_list();
+    }
+
+    /// 끝난 판의 기록과 연승, 같은 크기의 판의 평균을 보인다.
+    fn show_summary_window(&mut self, ctx: &egui::Context) {
+        let Some(result) = self.summary else {
+            return;
+        };
+        let mut open = true;
+        let mut new_game = false;
+        Window::new("Game summary")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                for line in result.summary() {
+                    ui.label(RichText::new(line).strong());
+                }
+                ui.separator();
+                for line in self.stats.summary(result.config) {
+                    ui.label(line);
+                }
+                ui.separator();
+                new_game = ui
+                    .add_sized(
+                        [self.button_width, self.button_height],
+                        Button::new("New game"),
+                    )
+                    .clicked();
+            });
+        if new_game {
+            self.new_game();
+        } else if !open {
+            self.summary = None;
+        }
+    }
+
+    /// 슬롯마다 작은 그림, 이름, 단계, 저장한 때와 Load, Delete 버튼을 보인다.
+    fn show_slot_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_slots;
+        // 창을 그리는 동안에는 self.slot_list를 빌리고 있으므로 누른 버튼은 나중에 처리한다.
+        let mut save = false;
+        let mut load = None;
+        let mut delete = None;
+        Window::new("Load / Save")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add_sized(
+                        [3.0 * self.button_width, self.button_height],
+                        TextEdit::singleline(&mut self.slot_name_input).hint_text("slot name"),
+                    );
+                    save = ui
+                        .add_sized([self.button_width, self.button_height], Button::new("Save"))
+                        .clicked();
+                });
+                ui.separator();
+                let now = SystemTime::now();
+                ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    Grid::new("slots").num_columns(4).show(ui, |ui| {
+                        for slot in &self.slot_list {
+                            match &slot.preview {
+                                Ok(preview) => {
+                                    let cell = vec2(4.0, 3.0);
+                                    let (response, painter) = ui.allocate_painter(
+                                        preview_size(preview, cell),
+                                        Sense::hover(),
+                                    );
+                                    draw_preview(&painter, response.rect.min, preview, cell);
+                                }
+                                Err(_) => {
+                                    ui.label("?");
+                                }
+                            }
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(&slot.name).strong());
+                                let info = match &slot.preview {
+                                    Ok(preview) if preview.is_completed => {
+                                        format!("Completed in {}", preview.step)
+                                    }
+                                    Ok(preview) => format!("Step {}", preview.step),
+                                    Err(e) => e.to_string(),
+                                };
+                                ui.label(info);
+                                ui.label(RichText::new(format_age(slot.modified, now)).weak());
+                            });
+                            if ui
+                                .add_enabled(slot.preview.is_ok(), Button::new("Load"))
+                                .clicked()
+                            {
+                                load = Some(slot.name.clone());
+                            }
+                            if ui.button("Delete").clicked() {
+                                delete = Some(slot.name.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        self.show_slots = open;
+        if save {
+            self.save_slot();
+        }
+        if let Some(name) = load {
+            self.load_slot(&name);
+        }
+        if let Some(name) = delete {
+            self.delete_slot(&name);
+        }
     }
 }
 
+fn stats_path() -> PathBuf {
+    Path::new(SAVE_DIR).join(STATS_FILE_NAME)
+}
+
 impl eframe::App for PuzzleGui {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.puzzle.check_solver();
-        if ctx.input(|input| input.key_pressed(Key::Z)) {
+        self.check_pending_level();
+        if ctx.wants_keyboard_input() {
+            // 레벨 코드를 입력하는 중에는 단축키를 쓰지 않는다.
+        } else if ctx.input(|input| input.key_pressed(Key::Z)) {
             self.puzzle.undo();
         } else if ctx.input(|input| input.key_pressed(Key::X)) {
             self.puzzle.redo();
         }
         if self.puzzle.is_completed() {
             self.info = "Completed".to_string();
+        } else if let Some(e) = self.puzzle.pour_error() {
+            self.info = e.to_string();
+        } else if self.puzzle.is_dead_end() {
+            self.info = "This move makes the puzzle unsolvable".to_string();
         } else if !self.puzzle.pouring_results_different_state() {
             self.info = "Cannot move".to_string()
         } else {
@@ -115,6 +431,8 @@ impl eframe::App for PuzzleGui {
                         ui.label(emoticon);
                         ui.label(self.puzzle.solver_remaining_step());
                         ui.label(&self.info);
+                        ui.label(RichText::new(&self.notice).color(Color32::from_rgb(255, 0, 0)));
+                        ui.label(RichText::new(self.puzzle.solver_stats()).weak());
                     });
                 });
             });
@@ -122,7 +440,9 @@ impl eframe::App for PuzzleGui {
             let size = ui.available_size();
             let (_response, painter) = ui.allocate_painter(size, Sense::click());
             ui.input(|input| {
-                if input.pointer.button_pressed(PointerButton::Primary) {
+                if self.show_slots {
+                    // Load/Save 창을 누른 것을 판을 누른 것으로 보지 않는다.
+                } else if input.pointer.button_pressed(PointerButton::Primary) {
                     let click_pos = input.pointer.interact_pos();
                     if let Some(pos) = click_pos {
                         self.viewport.on_left_click(&pos, &mut self.puzzle);
@@ -139,6 +459,8 @@ impl eframe::App for PuzzleGui {
             self.viewport.resize(size[0], size[1]);
             self.viewport.draw_puzzle(&painter, &self.puzzle);
         });
+        self.finish_game();
+        self.autosave();
         TopBottomPanel::bottom("bottom_panel")
             .exact_height(self.bottom_panel_height)
             .show(ctx, |ui| {
@@ -157,7 +479,7 @@ impl eframe::App for PuzzleGui {
                                 )
                                 .clicked()
                             {
-                                self.puzzle.reset();
+                                self.new_game();
                             }
                             if ui
                                 .add_sized(
@@ -186,6 +508,38 @@ impl eframe::App for PuzzleGui {
                             {
                                 self.puzzle.redo();
                             }
+                            // redo가 갈 가지를 고른다.
+                            let has_branches = self.puzzle.n_redo_branches() > 1;
+                            if ui.add_enabled(has_branches, Button::new("<")).clicked() {
+                                self.puzzle.cycle_redo_branch(false);
+                            }
+                            ui.label(self.puzzle.redo_branch_text());
+                            if ui.add_enabled(has_branches, Button::new(">")).clicked() {
+                                self.puzzle.cycle_redo_branch(true);
+                            }
+                            ui.checkbox(&mut self.mystery, "Mystery");
+                            ComboBox::from_id_salt("pour rule")
+                                .selected_text(self.pour_rule.to_string())
+                                .show_ui(ui, |ui| {
+                                    for rule in PourRule::ALL {
+                                        ui.selectable_value(
+                                            &mut self.pour_rule,
+                                            rule,
+                                            rule.to_string(),
+                                        );
+                                    }
+                                });
+                            ComboBox::from_id_salt("difficulty")
+                                .selected_text(self.difficulty.to_string())
+                                .show_ui(ui, |ui| {
+                                    for difficulty in Difficulty::ALL {
+                                        ui.selectable_value(
+                                            &mut self.difficulty,
+                                            difficulty,
+                                            difficulty.to_string(),
+                                        );
+                                    }
+                                });
                         });
                         ui.horizontal(|ui| {
                             if ui
@@ -197,10 +551,49 @@ impl eframe::App for PuzzleGui {
                             {
                                 self.puzzle.reset_solver_hint();
                             }
+                            let mut strategy = self.puzzle.solver_strategy();
+                            ComboBox::from_id_salt("solver strategy")
+                                .selected_text(strategy.to_string())
+                                .show_ui(ui, |ui| {
+                                    for s in Strategy::ALL {
+                                        ui.selectable_value(&mut strategy, s, s.to_string());
+                                    }
+                                });
+                            if strategy != self.puzzle.solver_strategy() {
+                                self.puzzle.set_solver_strategy(strategy);
+                            }
+                            ui.add_space(SPACE_X);
+                            ui.add_sized(
+                                [3.0 * self.button_width, self.button_height],
+                                TextEdit::singleline(&mut self.level_code_input),
+                            );
+                            if ui
+                                .add_sized(
+                                    [self.button_width, self.button_height],
+                                    Button::new("Open code"),
+                                )
+                                .clicked()
+                            {
+                                self.open_level_code();
+                            }
+                            if ui
+                                .add_sized(
+                                    [self.button_width, self.button_height],
+                                    Button::new("Load/Save"),
+                                )
+                                .clicked()
+                            {
+                                self.show_slots = !self.show_slots;
+                                self.refresh_slot_list();
+                            }
                         });
                         ui.end_row();
                     });
             });
+        if self.show_slots {
+            self.show_slot_window(ctx);
+        }
+        self.show_summary_window(ctx);
         // 0.1초 (100ms) 후에 UI 갱신을 요청합니다.
         // 이것이 UI를 "주기적으로 업데이트"하는 방법입니다.
         ctx.request_repaint_after(std::time::Duration::from_millis(100));
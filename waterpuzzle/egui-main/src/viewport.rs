@@ -1,10 +1,13 @@
 use eframe::egui::{
-    Align2, CornerRadius, FontId, Painter, Pos2, Rect, Stroke, StrokeKind, pos2, vec2,
+    Align2, CornerRadius, FontId, Painter, Pos2, Rect, Stroke, StrokeKind, Vec2, pos2, vec2,
+};
+use puzzle::{puzzle::Puzzle, save_file::Preview};
+use state::{
+    Water,
+    beaker::{NO_WATER, UNKNOWN_WATER},
 };
-use puzzle::puzzle::Puzzle;
-use state::{MAX_WATERS, beaker::NO_WATER};
 
-use crate::{BLACK, COLORS};
+use crate::{BLACK, LIGHT_GREY, RED, water_color};
 
 const OFFSET_X: f32 = 30.0;
 const OFFSET_Y: f32 = 60.0;
@@ -21,6 +24,7 @@ const HINT_DEST_ALPHA: f32 = 0.75;
 pub struct Viewport {
     n_rows: usize,
     n_cols: usize,
+    capacity: usize,
 
     beaker_width: f32,
     beaker_height: f32,
@@ -29,10 +33,11 @@ pub struct Viewport {
 }
 
 impl Viewport {
-    pub fn new(n_rows: usize, n_cols: usize) -> Self {
+    pub fn new(n_rows: usize, n_cols: usize, capacity: usize) -> Self {
         Self {
             n_rows,
             n_cols,
+            capacity,
             beaker_width: 0.0,
             beaker_height: 0.0,
             water_height: 0.0,
@@ -45,7 +50,7 @@ impl Viewport {
         self.beaker_width = (net_width + UNKNOWN_CORRECTION) / self.n_cols as f32;
         let net_height = height - OFFSET_Y - self.n_rows as f32 * SPACE_Y;
         self.beaker_height = net_height / self.n_rows as f32;
-        self.water_height = self.beaker_height / MAX_WATERS as f32;
+        self.water_height = self.beaker_height / self.capacity as f32;
         self.selected_shift = self.water_height / 2.0;
     }
 
@@ -55,6 +60,9 @@ impl Viewport {
     pub fn n_cols(&self) -> usize {
         self.n_cols
     }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
 
     pub fn row(&self, index: usize) -> usize {
         index / self.n_cols
@@ -98,7 +106,7 @@ impl Viewport {
         self.beaker_x(col)
     }
     pub fn water_y(&self, row: usize, water_index: usize) -> f32 {
-        self.beaker_y(row) + (MAX_WATERS - water_index - 1) as f32 * self.water_height
+        self.beaker_y(row) + (self.capacity - water_index - 1) as f32 * self.water_height
     }
     pub fn water_xy(&self, row: usize, col: usize, water_index: usize) -> (f32, f32) {
         (self.beaker_x(col), self.water_y(row, water_index))
@@ -125,11 +133,16 @@ impl Viewport {
     }
 
     pub fn on_right_click(&self, p: &Pos2, puzzle: &mut Puzzle) {
-        puzzle.on_right_click(self.index_of_beaker_which_contains(p));
+        let clicked_beaker_index = self
+            .index_of_beaker_which_contains(p)
+            .filter(|&index| index < puzzle.n_beakers());
+        puzzle.on_right_click(clicked_beaker_index);
     }
 
     pub fn on_left_click(&self, p: &Pos2, puzzle: &mut Puzzle) {
-        if let Some(clicked_beaker_index) = self.index_of_beaker_which_contains(p) {
+        if let Some(clicked_beaker_index) = self.index_of_beaker_which_contains(p)
+            && clicked_beaker_index < puzzle.n_beakers()
+        {
             puzzle.on_left_click(clicked_beaker_index);
         }
     }
@@ -144,17 +157,30 @@ impl Viewport {
         };
         let row = self.row(beaker_index);
         let col = self.col(beaker_index);
-        for i in 0..MAX_WATERS {
+        for i in 0..self.capacity {
             let mut water_rect = self.water_rect(row, col, i);
             if selected {
                 water_rect = water_rect.translate(vec2(0.0, -self.selected_shift));
             }
             let water = beaker.water(i);
-            if water != NO_WATER {
+            if beaker.is_hidden(i) {
+                painter.rect_filled(
+                    water_rect,
+                    CornerRadius::ZERO,
+                    LIGHT_GREY.linear_multiply(alpha),
+                );
+                painter.text(
+                    water_rect.center(),
+                    Align2::CENTER_CENTER,
+                    "?",
+                    FontId::monospace(20.0),
+                    BLACK,
+                );
+            } else if water != NO_WATER {
                 painter.rect_filled(
                     water_rect,
                     CornerRadius::ZERO,
-                    COLORS[water as usize].linear_multiply(alpha),
+                    water_color(water).linear_multiply(alpha),
                 );
                 painter.text(
                     water_rect.center(),
@@ -177,12 +203,13 @@ impl Viewport {
         if selected {
             beaker_rect = beaker_rect.translate(vec2(0.0, -self.selected_shift));
         }
-        painter.rect_stroke(
-            beaker_rect,
-            CornerRadius::ZERO,
-            Stroke::new(1.0, BLACK.linear_multiply(alpha)),
-            StrokeKind::Outside,
-        );
+        // 선택한 비커에서 부으면 풀 수 없게 되는 비커는 빨갛게 두른다.
+        let stroke = if puzzle.is_dead_target_index(beaker_index) {
+            Stroke::new(3.0, RED)
+        } else {
+            Stroke::new(1.0, BLACK.linear_multiply(alpha))
+        };
+        painter.rect_stroke(beaker_rect, CornerRadius::ZERO, stroke, StrokeKind::Outside);
     }
 
     pub fn draw_puzzle(&self, painter: &Painter, puzzle: &Puzzle) {
@@ -191,3 +218,38 @@ impl Viewport {
         }
     }
 }
+
+/// 슬롯 목록의 작은 그림의 크기. 물 한 칸을 cell 크기로 그리고 비커 사이는 한 칸씩 띄운다.
+pub fn preview_size(preview: &Preview, cell: Vec2) -> Vec2 {
+    let capacity = preview.config.capacity as f32;
+    vec2(
+        (2 * preview.n_cols) as f32 * cell.x,
+        preview.n_rows as f32 * (capacity + 1.0) * cell.y,
+    )
+}
+
+pub fn draw_preview(painter: &Painter, origin: Pos2, preview: &Preview, cell: Vec2) {
+    let capacity = preview.config.capacity;
+    for (i, &waters) in preview.cord.iter().enumerate() {
+        let (row, col) = (i / preview.n_cols, i % preview.n_cols);
+        let left = origin.x + (2 * col) as f32 * cell.x;
+        let top = origin.y + (row * (capacity + 1)) as f32 * cell.y;
+        for order in 0..capacity {
+            let color = match ((waters >> (8 * order)) & 0xFF) as Water {
+                NO_WATER => continue,
+                UNKNOWN_WATER => LIGHT_GREY,
+                water => water_color(water),
+            };
+            let y = top + (capacity - 1 - order) as f32 * cell.y;
+            let rect = Rect::from_min_size(pos2(left, y), cell);
+            painter.rect_filled(rect, CornerRadius::ZERO, color);
+        }
+        let rect = Rect::from_min_size(pos2(left, top), vec2(cell.x, capacity as f32 * cell.y));
+        painter.rect_stroke(
+            rect,
+            CornerRadius::ZERO,
+            Stroke::new(1.0, BLACK),
+            StrokeKind::Outside,
+        );
+    }
+}
@@ -1,20 +1,33 @@
 use std::{
     error::Error,
     fs::File,
-    io::{BufReader, BufWriter, Read, Write},
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 use iced::{
     Element, Event, Length, Point, Rectangle, Renderer, Size, Subscription, Theme, alignment,
     event, keyboard, mouse,
-    widget::{Space, button, canvas, column, row, text},
+    widget::{
+        Column, Space, button, canvas, checkbox, column, pick_list, row, scrollable, text,
+        text_input,
+    },
     window,
 };
-use puzzle::puzzle::Puzzle;
+use puzzle::{
+    generator::{Difficulty, PendingLevel},
+    puzzle::Puzzle,
+    save_file::{self, LoadError},
+    slots::{AUTOSAVE_SLOT, SlotInfo, SlotManager, format_age},
+    solver::Strategy,
+    stats::{GameResult, STATS_FILE_NAME, Statistics},
+};
+use state::config::{PourRule, PuzzleConfig};
 
 use crate::{
     RED,
-    viewport::{OFFSET_X, SPACE_X, Viewport},
+    viewport::{OFFSET_X, PreviewCanvas, SPACE_X, Viewport},
 };
 
 const HINT_ENABLED: bool = false;
@@ -30,28 +43,65 @@ const BUTTON_HEIGHT: f32 = 30.0;
 #[allow(dead_code)]
 const SMALL_ROW_WIDTH: f32 = 20.0;
 
+// 슬롯이 생기기 전에 쓰던 저장 파일. 자동 저장 슬롯이 없을 때만 읽는다.
 const FILE_NAME: &str = "save.wp";
+const SAVE_DIR: &str = "saves";
 
 #[derive(Debug, Clone)]
 pub enum Message {
     // WindowCloseRequested,
     WindowResized(Size),
     NewGame,
+    MysteryToggled(bool),
+    PourRuleSelected(PourRule),
+    DifficultySelected(Difficulty),
+    StrategySelected(Strategy),
     UndoAll,
     Undo,
     Redo,
+    PreviousBranch,
+    NextBranch,
     Hint,
     LButtonReleased(Point),
     RButtonReleased(Point),
     KeyPressed(keyboard::key::Physical),
+    LevelCodeChanged(String),
+    OpenLevelCode,
+    ToggleSlots,
+    SlotNameChanged(String),
+    SaveSlot,
+    LoadSlot(String),
+    DeleteSlot(String),
+    CloseSummary,
     Tick,
 }
 
 pub struct Gui {
     width: f32,
+    height: f32,
     puzzle: Puzzle,
     viewport: Viewport,
     info: String,
+    // 레벨 코드나 글자로 적은 판을 넣는 칸. 새 판이 만들어지면 그 판의 코드로 바뀐다.
+    level_code_input: String,
+    notice: String,
+    // 다음 "New game"을 "Mystery" 판으로 만들지
+    mystery: bool,
+    // 다음 "New game"에서 쓸 붓기 규칙
+    pour_rule: PourRule,
+    // 다음 "New game"의 어려움
+    difficulty: Difficulty,
+    // "New game"에서 찾고 있는 판. 찾는 동안 지금 판을 그대로 둔다.
+    pending_level: Option<PendingLevel>,
+    slots: SlotManager,
+    // 판 대신 Load/Save 목록을 보이는지
+    show_slots: bool,
+    slot_name_input: String,
+    // 목록을 열거나 슬롯을 바꿀 때 다시 읽는다.
+    slot_list: Vec<SlotInfo>,
+    stats: Statistics,
+    // 방금 끝난 판. 판 대신 요약을 보인다. 닫으면 None
+    summary: Option<GameResult>,
     cache: canvas::Cache,
 }
 
@@ -62,26 +112,150 @@ impl Default for Gui {
 }
 
 impl Gui {
-    pub fn new(n_rows: usize, n_cols: usize) -> Self {
+    pub fn new(n_rows: usize, n_cols: usize, config: PuzzleConfig) -> Self {
+        assert!(config.n_beakers() <= n_rows * n_cols);
         Self::from_puzzle_and_viewport(
-            Puzzle::random_new(n_rows * n_cols),
-            Viewport::new(n_rows, n_cols),
+            Puzzle::random_new(config),
+            Viewport::new(n_rows, n_cols, config.capacity),
         )
     }
 
     pub fn from_puzzle_and_viewport(puzzle: Puzzle, viewport: Viewport) -> Self {
-        Self {
-            width: 0.0, // anything
+        let (stats, notice) = match Statistics::load_from(&stats_path()) {
+            Ok(stats) => (stats, "".to_string()),
+            Err(e) => (Statistics::default(), format!("Stats: {e}")),
+        };
+        let mut gui = Self {
+            width: 0.0,  // anything
+            height: 0.0, // anything
             puzzle,
             viewport,
             info: "".to_string(),
+            level_code_input: "".to_string(),
+            notice,
+            mystery: false,
+            pour_rule: PourRule::default(),
+            difficulty: Difficulty::default(),
+            pending_level: None,
+            slots: SlotManager::new(SAVE_DIR),
+            show_slots: false,
+            slot_name_input: "".to_string(),
+            slot_list: Vec::new(),
+            stats,
+            summary: None,
             cache: canvas::Cache::new(),
+        };
+        gui.sync_level_code_input();
+        gui
+    }
+
+    fn sync_level_code_input(&mut self) {
+        self.mystery = self.puzzle.config().mystery;
+        self.pour_rule = self.puzzle.config().pour_rule;
+        self.level_code_input = self
+            .puzzle
+            .level_code()
+            .map(|level_code| level_code.to_string())
+            .unwrap_or_default();
+    }
+
+    fn open_level_code(&mut self) {
+        match Puzzle::from_code(&self.level_code_input) {
+            Ok(puzzle) => {
+                let n_cols = self.viewport.n_cols();
+                let n_rows = puzzle.config().n_beakers().div_ceil(n_cols);
+                self.set_puzzle(puzzle, n_rows, n_cols);
+                self.notice.clear();
+            }
+            Err(e) => self.notice = e,
+        }
+    }
+
+    fn set_puzzle(&mut self, puzzle: Puzzle, n_rows: usize, n_cols: usize) {
+        // 다른 판을 열었으므로 찾던 판은 버린다.
+        self.pending_level = None;
+        self.give_up();
+        self.viewport = Viewport::new(n_rows, n_cols, puzzle.config().capacity);
+        self.viewport
+            .resize(self.width, self.canvas_height(self.height));
+        self.puzzle = puzzle;
+        self.sync_level_code_input();
+    }
+
+    fn new_game(&mut self) {
+        let config = self
+            .puzzle
+            .config()
+            .with_mystery(self.mystery)
+            .with_pour_rule(self.pour_rule);
+        // 앞서 찾던 판은 버린다.
+        self.pending_level = PendingLevel::spawn(config, self.difficulty);
+        match &self.pending_level {
+            Some(pending) => {
+                self.notice = format!("{}: looking for a level...", pending.difficulty())
+            }
+            None => self.start_game(|puzzle| puzzle.reset_with(config)),
+        }
+    }
+
+    /// 찾던 판이 나왔으면 그 판으로 새로 시작한다.
+    fn check_pending_level(&mut self) {
+        let Some(pending) = self.pending_level.take() else {
+            return;
+        };
+        match pending.check() {
+            None => self.pending_level = Some(pending),
+            Some(Some(level)) => {
+                self.start_game(|puzzle| puzzle.reset_with_level_code(level.level_code))
+            }
+            Some(None) => {
+                self.start_game(|puzzle| puzzle.reset_with(pending.config()));
+                self.notice = format!("No {} level found", pending.difficulty());
+            }
+        }
+    }
+
+    fn start_game(&mut self, reset: impl FnOnce(&mut Puzzle)) {
+        self.give_up();
+        reset(&mut self.puzzle);
+        self.sync_level_code_input();
+        self.notice.clear();
+        self.summary = None;
+    }
+
+    /// 풀지 않고 넘어가는 판을 진 판으로 기록한다.
+    fn give_up(&mut self) {
+        if let Some(result) = self.puzzle.give_up() {
+            self.record_game(result);
+        }
+    }
+
+    /// 판을 다 풀었으면 기록하고 요약을 보인다.
+    fn finish_game(&mut self) {
+        if let Some(result) = self.puzzle.finish_game() {
+            self.record_game(result);
+            self.summary = Some(result);
         }
     }
+
+    fn record_game(&mut self, result: GameResult) {
+        self.stats.record(result);
+        if let Err(e) = self.stats.save_to(&stats_path()) {
+            self.notice = format!("Stats: {e}");
+        }
+    }
+
+    /// 저장 파일이 없으면 새 판을 만든다. 파일을 읽지 못하면 새 판을 만들고 이유를 알린다.
     pub fn load_or_new() -> Self {
         match Self::load() {
             Ok((puzzle, view)) => Self::from_puzzle_and_viewport(puzzle, view),
-            Err(_) => Self::new(3, 5),
+            Err(e) => {
+                let mut gui = Self::new(3, 5, PuzzleConfig::default());
+                if !matches!(&e, LoadError::Io(e) if e.kind() == ErrorKind::NotFound) {
+                    gui.notice = format!("Load: {e}");
+                }
+                gui
+            }
         }
     }
 
@@ -89,46 +263,83 @@ impl Gui {
         total_height - TOP_PANEL_HEIGHT - BOTTOM_PANEL_HEIGHT
     }
 
+    /// 자동 저장 슬롯에 저장한다.
     pub fn save(&self) -> Result<(), Box<dyn Error>> {
-        let file = File::create(FILE_NAME)?;
-        let mut writer = BufWriter::new(file);
-
-        writer.write_all(&(self.viewport.n_rows() as u32).to_le_bytes())?;
-        writer.write_all(&(self.viewport.n_cols() as u32).to_le_bytes())?;
-        self.puzzle.save(&mut writer)?;
-        writer.flush()?;
-        Ok(())
+        self.slots.save(
+            AUTOSAVE_SLOT,
+            self.viewport.n_rows(),
+            self.viewport.n_cols(),
+            &self.puzzle,
+        )
     }
 
-    pub fn load() -> Result<(Puzzle, Viewport), Box<dyn Error>> {
-        let file = File::open(FILE_NAME)?;
-        let mut reader = BufReader::new(file);
+    /// 자동 저장 슬롯을 읽는다. 슬롯이 없으면 예전 저장 파일을 읽는다.
+    pub fn load() -> Result<(Puzzle, Viewport), LoadError> {
+        let (n_rows, n_cols, puzzle) = match SlotManager::new(SAVE_DIR).load(AUTOSAVE_SLOT) {
+            Err(LoadError::Io(e)) if e.kind() == ErrorKind::NotFound => {
+                let file = File::open(FILE_NAME)?;
+                save_file::load(&mut BufReader::new(file))?
+            }
+            loaded => loaded?,
+        };
+        let view = Viewport::new(n_rows, n_cols, puzzle.config().capacity);
+        Ok((puzzle, view))
+    }
 
-        let mut buf = [0; 4];
-        reader.read_exact(&mut buf)?;
-        let n_rows = u32::from_le_bytes(buf) as usize;
+    fn autosave(&mut self) {
+        let (n_rows, n_cols) = (self.viewport.n_rows(), self.viewport.n_cols());
+        if let Err(e) = self.slots.autosave(n_rows, n_cols, &self.puzzle) {
+            self.notice = format!("Autosave: {e}");
+        }
+    }
 
-        reader.read_exact(&mut buf)?;
-        let n_cols = u32::from_le_bytes(buf) as usize;
+    fn refresh_slot_list(&mut self) {
+        match self.slots.list() {
+            Ok(slot_list) => self.slot_list = slot_list,
+            Err(e) => self.notice = format!("{}: {e}", self.slots.dir().display()),
+        }
+    }
 
-        let mut puzzle = Puzzle::empty_new(n_rows * n_cols);
-        puzzle.load(&mut reader)?;
+    fn save_slot(&mut self) {
+        let name = self.slot_name_input.trim();
+        let (n_rows, n_cols) = (self.viewport.n_rows(), self.viewport.n_cols());
+        match self.slots.save(name, n_rows, n_cols, &self.puzzle) {
+            Ok(()) => self.notice.clear(),
+            Err(e) => self.notice = e.to_string(),
+        }
+        self.refresh_slot_list();
+    }
 
-        let view = Viewport::new(n_rows, n_cols);
-        Ok((puzzle, view))
+    fn load_slot(&mut self, name: String) {
+        match self.slots.load(&name) {
+            Ok((n_rows, n_cols, puzzle)) => {
+                self.set_puzzle(puzzle, n_rows, n_cols);
+                self.slot_name_input = name;
+                self.show_slots = false;
+                self.notice.clear();
+            }
+            Err(e) => self.notice = format!("{name}: {e}"),
+        }
+    }
+
+    fn delete_slot(&mut self, name: String) {
+        if let Err(e) = self.slots.delete(&name) {
+            self.notice = format!("{name}: {e}");
+        }
+        self.refresh_slot_list();
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
         Subscription::batch(vec![
             // Listen for window events (resize, keyboard)
-            event::listen_with(|event, _status, _id| match event {
+            event::listen_with(|event, status, _id| match event {
                 Event::Window(window::Event::Resized(size)) => Some(Message::WindowResized(size)),
-                Event::Keyboard(keyboard_event) => match keyboard_event {
-                    keyboard::Event::KeyPressed { physical_key, .. } => {
-                        Some(Message::KeyPressed(physical_key))
-                    }
-                    _ => None,
-                },
+                // 레벨 코드 입력칸이 가져간 키는 단축키로 쓰지 않는다.
+                Event::Keyboard(keyboard::Event::KeyPressed { physical_key, .. })
+                    if status == event::Status::Ignored =>
+                {
+                    Some(Message::KeyPressed(physical_key))
+                }
                 _ => None,
             }),
             // Periodic tick for updates (e.g., solver animation, info text update)
@@ -158,13 +369,20 @@ impl Gui {
             // Message::WindowCloseRequested => {let _= self.save();},
             Message::WindowResized(size) => {
                 self.width = size.width;
+                self.height = size.height;
                 self.viewport
                     .resize(size.width, self.canvas_height(size.height))
             }
-            Message::NewGame => self.puzzle.reset(),
+            Message::NewGame => self.new_game(),
+            Message::MysteryToggled(mystery) => self.mystery = mystery,
+            Message::PourRuleSelected(pour_rule) => self.pour_rule = pour_rule,
+            Message::DifficultySelected(difficulty) => self.difficulty = difficulty,
+            Message::StrategySelected(strategy) => self.puzzle.set_solver_strategy(strategy),
             Message::UndoAll => self.puzzle.undo_all(),
             Message::Undo => self.puzzle.undo(),
             Message::Redo => self.puzzle.redo(),
+            Message::PreviousBranch => self.puzzle.cycle_redo_branch(false),
+            Message::NextBranch => self.puzzle.cycle_redo_branch(true),
             Message::Hint => self.puzzle.reset_solver_hint(),
             // Message::Hint => self.puzzle.apply_solver_hint(),
             Message::LButtonReleased(point) => {
@@ -180,10 +398,26 @@ impl Gui {
                     self.puzzle.redo();
                 }
             }
+            Message::LevelCodeChanged(level_code) => self.level_code_input = level_code,
+            Message::OpenLevelCode => self.open_level_code(),
+            Message::ToggleSlots => {
+                self.show_slots = !self.show_slots;
+                self.refresh_slot_list();
+            }
+            Message::SlotNameChanged(name) => self.slot_name_input = name,
+            Message::SaveSlot => self.save_slot(),
+            Message::LoadSlot(name) => self.load_slot(name),
+            Message::DeleteSlot(name) => self.delete_slot(name),
+            Message::CloseSummary => self.summary = None,
             Message::Tick => {
                 self.puzzle.check_solver();
+                self.check_pending_level();
                 if self.puzzle.is_completed() {
                     self.info = "Completed".to_string();
+                } else if let Some(e) = self.puzzle.pour_error() {
+                    self.info = e.to_string();
+                } else if self.puzzle.is_dead_end() {
+                    self.info = "This move makes the puzzle unsolvable".to_string();
                 } else if !self.puzzle.pouring_results_different_state() {
                     self.info = "Cannot move".to_string();
                 } else {
@@ -191,10 +425,13 @@ impl Gui {
                 }
             }
         }
+        self.finish_game();
+        self.autosave();
     }
 
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         let row_width_half = (self.width - OFFSET_X) / 2.0;
+        let has_branches = self.puzzle.n_redo_branches() > 1;
         column![
             row![
                 Space::with_width(OFFSET_X),
@@ -205,12 +442,20 @@ impl Gui {
                         "  {}  {}",
                         self.puzzle.solver_remaining_step(),
                         self.info
-                    ))
+                    )),
+                    text(format!("  {}", self.notice)).color(RED),
+                    text(format!("  {}", self.puzzle.solver_stats())).size(12),
                 ] // .width(row_width_half),
             ]
             .height(TOP_PANEL_HEIGHT)
             .align_y(alignment::Vertical::Center),
-            canvas(self).width(Length::Fill).height(Length::Fill),
+            if self.show_slots {
+                self.slot_view()
+            } else if let Some(result) = &self.summary {
+                self.summary_view(result)
+            } else {
+                canvas(self).width(Length::Fill).height(Length::Fill).into()
+            },
             row![
                 Space::with_width(OFFSET_X),
                 row![
@@ -221,13 +466,47 @@ impl Gui {
                     button("Undo(Z)").on_press(Message::Undo),
                     Space::with_width(SPACE_X),
                     button("Redo(X)").on_press(Message::Redo),
+                    Space::with_width(SPACE_X),
+                    // redo가 갈 가지를 고른다.
+                    button("<").on_press_maybe(has_branches.then_some(Message::PreviousBranch)),
+                    text(format!(" {} ", self.puzzle.redo_branch_text())),
+                    button(">").on_press_maybe(has_branches.then_some(Message::NextBranch)),
+                    Space::with_width(SPACE_X),
+                    checkbox("Mystery", self.mystery).on_toggle(Message::MysteryToggled),
+                    Space::with_width(SPACE_X),
+                    pick_list(
+                        PourRule::ALL,
+                        Some(self.pour_rule),
+                        Message::PourRuleSelected
+                    ),
+                    Space::with_width(SPACE_X),
+                    pick_list(
+                        Difficulty::ALL,
+                        Some(self.difficulty),
+                        Message::DifficultySelected
+                    ),
                 ]
                 .width(row_width_half),
                 if HINT_ENABLED {
                     button("Hint").on_press(Message::Hint)
                 } else {
                     button("Hint")
-                }
+                },
+                Space::with_width(SPACE_X),
+                pick_list(
+                    Strategy::ALL,
+                    Some(self.puzzle.solver_strategy()),
+                    Message::StrategySelected
+                ),
+                Space::with_width(SPACE_X),
+                text_input("level code or board", &self.level_code_input)
+                    .on_input(Message::LevelCodeChanged)
+                    .on_submit(Message::OpenLevelCode)
+                    .width(3.0 * BUTTON_WIDTH),
+                Space::with_width(SPACE_X),
+                button("Open code").on_press(Message::OpenLevelCode),
+                Space::with_width(SPACE_X),
+                button("Load/Save").on_press(Message::ToggleSlots),
             ]
             .height(BOTTOM_PANEL_HEIGHT)
             .align_y(alignment::Vertical::Top)
@@ -236,6 +515,95 @@ impl Gui {
     }
 }
 
+impl Gui {
+    /// 끝난 판의 기록과 연승, 같은 크기의 판의 평균을 보인다.
+    fn summary_view(&self, result: &GameResult) -> Element<'_, Message> {
+        let lines = result
+            .summary()
+            .into_iter()
+            .map(|line| text(line).size(20).into());
+        let stats = self
+            .stats
+            .summary(result.config)
+            .into_iter()
+            .map(|line| text(line).into());
+        column![
+            Column::with_children(lines).spacing(SPACE_X),
+            Column::with_children(stats).spacing(SPACE_X),
+            row![
+                button("New game").on_press(Message::NewGame),
+                button("Close").on_press(Message::CloseSummary),
+            ]
+            .spacing(SPACE_X),
+        ]
+        .spacing(2.0 * SPACE_X)
+        .padding(OFFSET_X)
+        .height(Length::Fill)
+        .into()
+    }
+
+    /// 슬롯마다 작은 그림, 이름, 단계, 저장한 때와 Load, Delete 버튼을 보인다.
+    fn slot_view(&self) -> Element<'_, Message> {
+        let now = SystemTime::now();
+        let slots = self.slot_list.iter().map(|slot| {
+            let thumbnail: Element<'_, Message> = match &slot.preview {
+                Ok(preview) => {
+                    let program = PreviewCanvas {
+                        preview,
+                        cell: Size::new(4.0, 3.0),
+                    };
+                    let size = program.size();
+                    canvas(program).width(size.width).height(size.height).into()
+                }
+                Err(_) => text("?").into(),
+            };
+            let info = match &slot.preview {
+                Ok(preview) if preview.is_completed => format!("Completed in {}", preview.step),
+                Ok(preview) => format!("Step {}", preview.step),
+                Err(e) => e.to_string(),
+            };
+            row![
+                thumbnail,
+                column![
+                    text(&slot.name),
+                    text(info),
+                    text(format_age(slot.modified, now)).size(12),
+                ]
+                .width(4.0 * BUTTON_WIDTH),
+                button("Load").on_press_maybe(
+                    slot.preview
+                        .is_ok()
+                        .then(|| Message::LoadSlot(slot.name.clone()))
+                ),
+                button("Delete").on_press(Message::DeleteSlot(slot.name.clone())),
+            ]
+            .spacing(SPACE_X)
+            .align_y(alignment::Vertical::Center)
+            .into()
+        });
+        column![
+            row![
+                text_input("slot name", &self.slot_name_input)
+                    .on_input(Message::SlotNameChanged)
+                    .on_submit(Message::SaveSlot)
+                    .width(3.0 * BUTTON_WIDTH),
+                button("Save").on_press(Message::SaveSlot),
+                button("Close").on_press(Message::ToggleSlots),
+            ]
+            .spacing(SPACE_X),
+            scrollable(Column::with_children(slots).spacing(SPACE_X)).height(Length::Fill),
+        ]
+        .spacing(SPACE_X)
+        .padding(OFFSET_X)
+        .height(Length::Fill)
+        .into()
+    }
+}
+
+fn stats_path() -> PathBuf {
+    Path::new(SAVE_DIR).join(STATS_FILE_NAME)
+}
+
 impl canvas::Program<Message> for Gui {
     // No internal state
     type State = ();
@@ -249,23 +617,20 @@ impl canvas::Program<Message> for Gui {
     ) -> (canvas::event::Status, Option<Message>) {
         type Status = canvas::event::Status;
         match _event {
-            canvas::Event::Mouse(event) => match event {
-                mouse::Event::ButtonReleased(button) => match button {
-                    mouse::Button::Left => match cursor.position() {
-                        Some(mut pos) => {
-                            pos.y -= TOP_PANEL_HEIGHT;
-                            (Status::Captured, Some(Message::LButtonReleased(pos)))
-                        }
-                        None => (Status::Ignored, None),
-                    },
-                    mouse::Button::Right | mouse::Button::Middle => match cursor.position() {
-                        Some(mut pos) => {
-                            pos.y -= TOP_PANEL_HEIGHT;
-                            (Status::Captured, Some(Message::RButtonReleased(pos)))
-                        }
-                        None => (Status::Ignored, None),
-                    },
-                    _ => (Status::Ignored, None),
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(button)) => match button {
+                mouse::Button::Left => match cursor.position() {
+                    Some(mut pos) => {
+                        pos.y -= TOP_PANEL_HEIGHT;
+                        (Status::Captured, Some(Message::LButtonReleased(pos)))
+                    }
+                    None => (Status::Ignored, None),
+                },
+                mouse::Button::Right | mouse::Button::Middle => match cursor.position() {
+                    Some(mut pos) => {
+                        pos.y -= TOP_PANEL_HEIGHT;
+                        (Status::Captured, Some(Message::RButtonReleased(pos)))
+                    }
+                    None => (Status::Ignored, None),
                 },
                 _ => (Status::Ignored, None),
             },
@@ -1,4 +1,5 @@
 use iced::Color;
+use state::Water;
 
 pub const RED: Color = Color::from_rgb(1.0, 0.0, 0.0); // FF0000 빨강, 선명한 빨강
 pub const ORANGE: Color = Color::from_rgb(1.0, 0.5, 0.0); // FF8000 주황, 밝고 경쾌한 주황.
@@ -39,5 +40,10 @@ pub const COLORS: &[Color] = &[
     BLACK,
 ];
 
+/// 팔레트보다 색이 많으면 팔레트를 돌려 쓴다. 물에는 색 번호도 함께 그리므로 구별할 수 있다.
+pub fn water_color(water: Water) -> Color {
+    COLORS[1 + (water as usize - 1) % (COLORS.len() - 1)]
+}
+
 pub mod gui;
 pub mod viewport;
\ No newline at end of file
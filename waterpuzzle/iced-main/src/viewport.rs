@@ -1,6 +1,9 @@
-use iced::{Point, Rectangle, Size, alignment, widget::canvas};
-use puzzle::puzzle::Puzzle;
-use state::{MAX_WATERS, beaker::NO_WATER};
+use iced::{Point, Rectangle, Renderer, Size, Theme, alignment, mouse, widget::canvas};
+use puzzle::{puzzle::Puzzle, save_file::Preview};
+use state::{
+    Water,
+    beaker::{NO_WATER, UNKNOWN_WATER},
+};
 
 use crate::*;
 
@@ -17,6 +20,7 @@ const HINT_DEST_ALPHA: f32 = 0.5;
 pub struct Viewport {
     n_rows: usize,
     n_cols: usize,
+    capacity: usize,
 
     beaker_width: f32,
     beaker_height: f32,
@@ -25,10 +29,11 @@ pub struct Viewport {
 }
 
 impl Viewport {
-    pub fn new(n_rows: usize, n_cols: usize) -> Self {
+    pub fn new(n_rows: usize, n_cols: usize, capacity: usize) -> Self {
         Self {
             n_rows,
             n_cols,
+            capacity,
             beaker_width: 0.0,
             beaker_height: 0.0,
             water_height: 0.0,
@@ -41,7 +46,7 @@ impl Viewport {
         self.beaker_width = net_width / self.n_cols as f32;
         let net_height = height - OFFSET_Y - self.n_rows as f32 * SPACE_Y;
         self.beaker_height = (net_height / self.n_rows as f32).floor();
-        self.water_height = (self.beaker_height / MAX_WATERS as f32).floor();
+        self.water_height = (self.beaker_height / self.capacity as f32).floor();
         self.selected_shift = (self.water_height / 2.0).floor();
     }
 
@@ -51,6 +56,9 @@ impl Viewport {
     pub fn n_cols(&self) -> usize {
         self.n_cols
     }
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
 
     pub fn row(&self, index: usize) -> usize {
         index / self.n_cols
@@ -97,7 +105,7 @@ impl Viewport {
         self.beaker_x(col)
     }
     pub fn water_y(&self, row: usize, water_index: usize) -> f32 {
-        self.beaker_y(row) + (MAX_WATERS - water_index - 1) as f32 * self.water_height
+        self.beaker_y(row) + (self.capacity - water_index - 1) as f32 * self.water_height
     }
     pub fn water_xy(&self, row: usize, col: usize, water_index: usize) -> (f32, f32) {
         (self.beaker_x(col), self.water_y(row, water_index))
@@ -127,11 +135,16 @@ impl Viewport {
     }
 
     pub fn on_right_click(&self, p: &Point, puzzle: &mut Puzzle) {
-        puzzle.on_right_click(self.index_of_beaker_which_contains(p));
+        let clicked_beaker_index = self
+            .index_of_beaker_which_contains(p)
+            .filter(|&index| index < puzzle.n_beakers());
+        puzzle.on_right_click(clicked_beaker_index);
     }
 
     pub fn on_left_click(&self, p: &Point, puzzle: &mut Puzzle) {
-        if let Some(clicked_beaker_index) = self.index_of_beaker_which_contains(p) {
+        if let Some(clicked_beaker_index) = self.index_of_beaker_which_contains(p)
+            && clicked_beaker_index < puzzle.n_beakers()
+        {
             puzzle.on_left_click(clicked_beaker_index);
         }
     }
@@ -148,18 +161,36 @@ impl Viewport {
         let row = self.row(beaker_index);
         let col = self.col(beaker_index);
         let water_size = self.water_size();
-        for i in 0..MAX_WATERS {
+        for i in 0..self.capacity {
             let mut water_pos = self.water_pos(row, col, i);
             if selected {
                 water_pos.y -= self.selected_shift;
             }
             let water = beaker.water(i);
-            if water != NO_WATER {
+            if beaker.is_hidden(i) {
+                frame.fill_rectangle(
+                    water_pos,
+                    water_size,
+                    canvas::Fill::from(LIGHT_GREY.scale_alpha(alpha)),
+                );
+                frame.fill_text(canvas::Text {
+                    content: "?".to_string(),
+                    position: Point::new(
+                        water_pos.x + self.beaker_width / 2.0,
+                        water_pos.y + self.water_height / 2.0,
+                    ),
+                    color: BLACK,
+                    size: iced::Pixels(20.0),
+                    horizontal_alignment: alignment::Horizontal::Center,
+                    vertical_alignment: alignment::Vertical::Center,
+                    ..Default::default()
+                });
+            } else if water != NO_WATER {
                 // let color = if hint_dst {COLORS[water as usize].scale_alpha(0.5)} else {COLORS[water as usize]};
                 frame.fill_rectangle(
                     water_pos,
                     water_size,
-                    canvas::Fill::from(COLORS[water as usize].scale_alpha(alpha)),
+                    canvas::Fill::from(water_color(water).scale_alpha(alpha)),
                 );
                 // Draw text for water index
                 frame.fill_text(canvas::Text {
@@ -189,11 +220,13 @@ impl Viewport {
         if selected {
             beaker_pos.y -= self.selected_shift;
         }
-        frame.stroke_rectangle(
-            beaker_pos,
-            self.beaker_size(),
-            canvas::Stroke::default().with_color(BLACK.scale_alpha(alpha)),
-        );
+        // 선택한 비커에서 부으면 풀 수 없게 되는 비커는 빨갛게 두른다.
+        let stroke = if puzzle.is_dead_target_index(beaker_index) {
+            canvas::Stroke::default().with_color(RED).with_width(3.0)
+        } else {
+            canvas::Stroke::default().with_color(BLACK.scale_alpha(alpha))
+        };
+        frame.stroke_rectangle(beaker_pos, self.beaker_size(), stroke);
     }
 
     pub fn draw_puzzle(&self, frame: &mut canvas::Frame, puzzle: &Puzzle) {
@@ -202,3 +235,57 @@ impl Viewport {
         }
     }
 }
+
+/// 슬롯 목록의 작은 그림. 물 한 칸을 cell 크기로 그리고 비커 사이는 한 칸씩 띄운다.
+pub struct PreviewCanvas<'a> {
+    pub preview: &'a Preview,
+    pub cell: Size,
+}
+
+impl PreviewCanvas<'_> {
+    pub fn size(&self) -> Size {
+        let capacity = self.preview.config.capacity as f32;
+        Size::new(
+            (2 * self.preview.n_cols) as f32 * self.cell.width,
+            self.preview.n_rows as f32 * (capacity + 1.0) * self.cell.height,
+        )
+    }
+}
+
+impl<Message> canvas::Program<Message> for PreviewCanvas<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let preview = self.preview;
+        let capacity = preview.config.capacity;
+        let cell = self.cell;
+        for (i, &waters) in preview.cord.iter().enumerate() {
+            let (row, col) = (i / preview.n_cols, i % preview.n_cols);
+            let left = (2 * col) as f32 * cell.width;
+            let top = (row * (capacity + 1)) as f32 * cell.height;
+            for order in 0..capacity {
+                let color = match ((waters >> (8 * order)) & 0xFF) as Water {
+                    NO_WATER => continue,
+                    UNKNOWN_WATER => LIGHT_GREY,
+                    water => water_color(water),
+                };
+                let y = top + (capacity - 1 - order) as f32 * cell.height;
+                frame.fill_rectangle(Point::new(left, y), cell, canvas::Fill::from(color));
+            }
+            frame.stroke_rectangle(
+                Point::new(left, top),
+                Size::new(cell.width, capacity as f32 * cell.height),
+                canvas::Stroke::default().with_color(BLACK),
+            );
+        }
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    fmt,
+    ops::RangeInclusive,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use astar::{
+    budget::SolveBudget, exaustive_astar::ExaustiveAStar, heuristic::MinimumPours,
+    strategy::SolverStrategy,
+};
+use state::{config::PuzzleConfig, level_code::LevelCode, state::State};
+
+const DEFAULT_MAX_ATTEMPTS: usize = 100;
+// 판을 기다리는 플레이어가 너무 오래 기다리지 않도록 Difficulty로 만들 때는 조금만 본다.
+const DIFFICULTY_MAX_ATTEMPTS: usize = 20;
+
+/// 판 하나를 확인할 때 펼칠 수 있는 상태의 기본 개수. 시간 대신 개수로 정하므로 같은 판은 언제나 같은 결과가 된다.
+pub const EVALUATE_BUDGET: SolveBudget = SolveBudget {
+    max_nodes: Some(200_000),
+    max_time: None,
+};
+
+/// 솔버로 확인한 판의 품질
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelQuality {
+    /// 최단 풀이의 길이
+    pub optimal_length: usize,
+    /// 펼친 상태 하나에서 부을 수 있는 곳의 평균 개수
+    pub branching_factor: f64,
+    /// 탐색 중에 만난, 더 부을 수 없는 미완성 상태의 개수
+    pub n_dead_ends: usize,
+}
+
+/// 새 판의 어려움. 최단 풀이의 길이로 나눈다.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    /// 솔버로 확인하지 않은 아무 판
+    #[default]
+    Any,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Self; 4] = [Self::Any, Self::Easy, Self::Normal, Self::Hard];
+
+    /// 색마다 capacity - 1 번 부어 모으는 것을 기준으로 한 최단 풀이의 길이. Any이면 None
+    pub fn optimal_lengths(self, config: PuzzleConfig) -> Option<RangeInclusive<usize>> {
+        let base = config.n_colors * config.capacity.saturating_sub(1);
+        let hard = base + base.div_ceil(10);
+        match self {
+            Self::Any => None,
+            Self::Easy => Some(0..=base),
+            Self::Normal => Some(base + 1..=hard),
+            Self::Hard => Some(hard + 1..=usize::MAX),
+        }
+    }
+
+    /// Any이면 None
+    pub fn generator(self, config: PuzzleConfig) -> Option<LevelGenerator> {
+        let optimal_lengths = self.optimal_lengths(config)?;
+        Some(
+            LevelGenerator::new(config, optimal_lengths).with_max_attempts(DIFFICULTY_MAX_ATTEMPTS),
+        )
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Any => "Any level",
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeneratedLevel {
+    pub level_code: LevelCode,
+    pub quality: LevelQuality,
+}
+
+/// 최단 풀이의 길이가 주어진 범위 안에 드는 판을 찾을 때까지 판을 만든다.
+/// 이미 거의 정리된 판, 풀 수 없는 판, 한도 안에 최단 풀이를 찾지 못한 판은 버린다.
+/// 가려진 층이 있는 판도 모든 층이 보이는 판으로 잰다.
+#[derive(Clone, Debug)]
+pub struct LevelGenerator {
+    config: PuzzleConfig,
+    optimal_lengths: RangeInclusive<usize>,
+    max_attempts: usize,
+    budget: SolveBudget,
+    // Some이면 seed, seed + 1, ...의 판을 차례로 본다. None이면 무작위로 고른다.
+    first_seed: Option<u64>,
+}
+
+impl LevelGenerator {
+    pub fn new(config: PuzzleConfig, optimal_lengths: RangeInclusive<usize>) -> Self {
+        Self {
+            config,
+            optimal_lengths,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            budget: EVALUATE_BUDGET,
+            first_seed: None,
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// 판 하나를 확인할 때의 한도. 한도에 닿은 판은 버린다.
+    pub fn with_budget(mut self, budget: SolveBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// 같은 seed에서는 언제나 같은 판을 돌려준다.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.first_seed = Some(seed);
+        self
+    }
+
+    /// max_attempts 번 안에 조건에 맞는 판을 찾지 못하면 None
+    pub fn generate(&self) -> Option<GeneratedLevel> {
+        self.generate_while(|| true)
+    }
+
+    /// 판을 하나 확인하기 전마다 keep_going()을 부르고, false이면 찾지 못한 것으로 끝낸다.
+    pub fn generate_while(&self, mut keep_going: impl FnMut() -> bool) -> Option<GeneratedLevel> {
+        // 가려진 층은 seed로 판을 만든 다음에 가리므로 층의 색은 같다.
+        let mut state = State::new(self.config.with_mystery(false));
+        for attempt in 0..self.max_attempts {
+            if !keep_going() {
+                return None;
+            }
+            let seed = match self.first_seed {
+                Some(first_seed) => {
+                    let seed = first_seed.wrapping_add(attempt as u64);
+                    state.generate_with_seed(seed);
+                    seed
+                }
+                None => state.random_generate(),
+            };
+            let Some(quality) = Self::evaluate_state(state.clone(), self.budget) else {
+                continue;
+            };
+            if self.optimal_lengths.contains(&quality.optimal_length) {
+                return Some(GeneratedLevel {
+                    level_code: LevelCode::new(self.config, seed),
+                    quality,
+                });
+            }
+        }
+        None
+    }
+
+    /// 풀 수 없거나 budget 안에 최단 풀이를 찾지 못한 판이면 None
+    pub fn evaluate(level_code: LevelCode, budget: SolveBudget) -> Option<LevelQuality> {
+        let mut state = State::new(level_code.config.with_mystery(false));
+        state.generate_with_seed(level_code.seed);
+        Self::evaluate_state(state, budget)
+    }
+
+    fn evaluate_state(state: State, budget: SolveBudget) -> Option<LevelQuality> {
+        // 멈추라는 신호를 보내지 않으므로 sender는 끝날 때까지 들고만 있는다.
+        let (_stop_tx, stop_rx) = mpsc::channel();
+        // 최단 풀이의 길이가 맞도록 admissible한 추정을 쓴다.
+        let mut astar = ExaustiveAStar::with_heuristic(MinimumPours);
+        astar.set_budget(budget);
+        let path = astar.find_path(state, stop_rx);
+        if astar.budget_exhausted() {
+            return None;
+        }
+        let path = path?;
+        Some(LevelQuality {
+            optimal_length: path.len() - 1,
+            branching_factor: astar.n_successors as f64 / astar.epoch.max(1) as f64,
+            n_dead_ends: astar.n_dead_ends,
+        })
+    }
+}
+
+/// 스레드에서 Difficulty에 맞는 판을 찾는 중. 찾는 동안 지금 판은 그대로 쓸 수 있다.
+/// `Solver`처럼 GUI가 check()로 결과를 기다린다. 버리면 스레드는 확인하던 판까지만 보고 끝난다.
+#[derive(Debug)]
+pub struct PendingLevel {
+    config: PuzzleConfig,
+    difficulty: Difficulty,
+    receiver: mpsc::Receiver<Option<GeneratedLevel>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PendingLevel {
+    /// difficulty가 Any이면 찾을 것이 없으므로 None
+    pub fn spawn(config: PuzzleConfig, difficulty: Difficulty) -> Option<Self> {
+        let generator = difficulty.generator(config)?;
+        let (sender, receiver) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled_for_thread = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            let level = generator.generate_while(|| !cancelled_for_thread.load(Ordering::Relaxed));
+            // 그 사이에 버려졌으면 받는 쪽이 없다.
+            let _ = sender.send(level);
+        });
+        Some(Self {
+            config,
+            difficulty,
+            receiver,
+            cancelled,
+        })
+    }
+
+    pub fn config(&self) -> PuzzleConfig {
+        self.config
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// 아직 찾고 있으면 None. 끝났으면 Some(찾은 판)이고, 조건에 맞는 판이 없었으면 Some(None)
+    pub fn check(&self) -> Option<Option<GeneratedLevel>> {
+        match self.receiver.try_recv() {
+            Ok(level) => Some(level),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(None),
+        }
+    }
+}
+
+impl Drop for PendingLevel {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
@@ -1,74 +1,180 @@
 use std::{
     error::Error,
-    io::{Read, Write},
+    io::{ErrorKind, Read, Write},
 };
 
-use state::Cord;
+use state::{Cord, Waters};
 
-#[derive(Debug, Default)]
+/// 부모 노드가 없다는 뜻. 저장 파일에서 뿌리 노드의 부모로 쓴다.
+const NO_PARENT: u32 = u32::MAX;
+
+/// 저장 파일에 적는 노드의 (부모, 고른 가지)
+pub(crate) type Link = (Option<usize>, usize);
+
+#[derive(Debug)]
+struct HistoryNode {
+    cord: Cord,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // redo가 갈 자식의 children 안의 위치. 마지막으로 지나간 가지를 가리킨다.
+    branch: usize,
+    // 뿌리에서 이 노드까지의 붓기 수
+    depth: usize,
+}
+
+impl HistoryNode {
+    fn new(cord: Cord, parent: Option<usize>, depth: usize) -> Self {
+        Self {
+            cord,
+            parent,
+            children: Vec::new(),
+            branch: 0,
+            depth,
+        }
+    }
+}
+
+/// 되돌리기 나무. 되돌린 다음 다른 붓기를 해도 원래 가지는 남고, redo는 고른 가지로 간다.
+#[derive(Debug)]
 pub struct History {
-    cords: Vec<Cord>,
-    step: usize,
+    // 만든 순서대로 놓인 노드들. nodes[0]이 뿌리이고 부모는 언제나 자식보다 앞에 있다.
+    nodes: Vec<HistoryNode>,
+    current: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(Cord::new())
+    }
 }
 
 impl History {
     pub fn new(cord: Cord) -> Self {
         Self {
-            cords: vec![cord],
-            step: 0,
+            nodes: vec![HistoryNode::new(cord, None, 0)],
+            current: 0,
         }
     }
 
     pub fn reset(&mut self, cord: Cord) {
-        self.cords = vec![cord];
-        self.step = 0;
+        *self = Self::new(cord);
     }
 
     pub fn step(&self) -> usize {
-        self.step
+        self.nodes[self.current].depth
+    }
+
+    /// 나무의 노드 개수. 처음 판의 노드가 있으므로 언제나 1 이상이다.
+    pub fn n_nodes(&self) -> usize {
+        self.nodes.len()
     }
 
+    /// 지금 노드의 판
+    pub fn current(&self) -> &Cord {
+        &self.nodes[self.current].cord
+    }
+
+    /// 처음 판
+    pub fn root(&self) -> &Cord {
+        &self.nodes[0].cord
+    }
+
+    /// 모든 노드의 판
+    pub fn cords(&self) -> impl Iterator<Item = &Cord> {
+        self.nodes.iter().map(|node| &node.cord)
+    }
+
+    /// 지금 노드에서 새 붓기를 한다. 같은 cord의 가지가 이미 있으면 그 가지로 간다.
     pub fn push(&mut self, cord: Cord) {
-        self.step += 1;
-        if self.step == self.cords.len() {
-            self.cords.push(cord);
-        } else {
-            self.cords[self.step] = cord;
-            self.cords.truncate(self.step + 1);
-        }
+        let current = &self.nodes[self.current];
+        let branch = match current
+            .children
+            .iter()
+            .position(|&child| self.nodes[child].cord == cord)
+        {
+            Some(branch) => branch,
+            None => {
+                let child = self.nodes.len();
+                let depth = current.depth + 1;
+                self.nodes
+                    .push(HistoryNode::new(cord, Some(self.current), depth));
+                let current = &mut self.nodes[self.current];
+                current.children.push(child);
+                current.children.len() - 1
+            }
+        };
+        let current = &mut self.nodes[self.current];
+        current.branch = branch;
+        self.current = current.children[branch];
     }
 
     pub fn undo(&mut self) -> Option<&Cord> {
-        if self.step == 0 {
-            None
-        } else {
-            self.step -= 1;
-            Some(&self.cords[self.step])
-        }
+        let parent = self.nodes[self.current].parent?;
+        self.current = parent;
+        Some(&self.nodes[parent].cord)
     }
 
     pub fn undo_all(&mut self) -> &Cord {
-        self.step = 0;
-        &self.cords[0]
+        self.current = 0;
+        &self.nodes[0].cord
+    }
+
+    /// 뿌리에서 지금 노드를 지나 고른 가지들을 따라 redo로 갈 수 있는 끝까지의 판들
+    pub fn main_line(&self) -> Vec<&Cord> {
+        let mut line = Vec::new();
+        let mut node = Some(self.current);
+        while let Some(i) = node {
+            line.push(&self.nodes[i].cord);
+            node = self.nodes[i].parent;
+        }
+        line.reverse();
+        let mut node = &self.nodes[self.current];
+        while let Some(&child) = node.children.get(node.branch) {
+            node = &self.nodes[child];
+            line.push(&node.cord);
+        }
+        line
     }
 
+    /// 고른 가지(`select_branch()`)로 한 걸음 간다.
     pub fn redo(&mut self) -> Option<&Cord> {
-        if self.step + 1 == self.cords.len() {
-            None
-        } else {
-            self.step += 1;
-            Some(&self.cords[self.step])
+        let current = &self.nodes[self.current];
+        let child = *current.children.get(current.branch)?;
+        self.current = child;
+        Some(&self.nodes[child].cord)
+    }
+
+    /// redo로 갈 수 있는 가지의 개수
+    pub fn n_branches(&self) -> usize {
+        self.nodes[self.current].children.len()
+    }
+
+    /// redo가 갈 가지. 0..n_branches()
+    pub fn branch(&self) -> usize {
+        self.nodes[self.current].branch
+    }
+
+    pub fn select_branch(&mut self, branch: usize) {
+        if branch < self.n_branches() {
+            self.nodes[self.current].branch = branch;
         }
     }
 
+    /// 노드의 개수, 지금 노드, 모든 노드의 cord를 적은 다음 노드마다 (부모, 고른 가지)를 적는다.
+    /// 가지가 없던 때의 파일은 (부모, 고른 가지) 없이 끝나므로 읽을 때 한 줄기로 본다.
     pub fn save<W: Write>(&self, bw: &mut W) -> Result<(), Box<dyn Error>> {
-        bw.write_all(&(self.cords.len() as u32).to_le_bytes())?;
-        bw.write_all(&(self.step as u32).to_le_bytes())?;
-        for cord in self.cords.iter() {
-            for x in cord {
+        bw.write_all(&(self.nodes.len() as u32).to_le_bytes())?;
+        bw.write_all(&(self.current as u32).to_le_bytes())?;
+        for node in self.nodes.iter() {
+            for x in &node.cord {
                 bw.write_all(&x.to_le_bytes())?;
             }
         }
+        for node in self.nodes.iter() {
+            let parent = node.parent.map_or(NO_PARENT, |parent| parent as u32);
+            bw.write_all(&parent.to_le_bytes())?;
+            bw.write_all(&(node.branch as u32).to_le_bytes())?;
+        }
         Ok(())
     }
 
@@ -76,26 +182,100 @@ impl History {
     pub fn load<R: Read>(&mut self, br: &mut R, n_beakers: usize) -> Result<(), Box<dyn Error>> {
         let mut buf = [0; 4];
 
-        // Read `n_coords`
+        // Read `n_nodes`
         br.read_exact(&mut buf)?;
-        let n_cords = u32::from_le_bytes(buf) as usize;
+        let n_nodes = u32::from_le_bytes(buf) as usize;
 
-        // Read `pos`
+        // Read `current`
         br.read_exact(&mut buf)?;
-        self.step = u32::from_le_bytes(buf) as usize;
-
-        // Initialize `cords` with the correct dimensions
-        self.cords = Vec::with_capacity(n_cords);
-        for _ in 0..n_cords {
+        let current = u32::from_le_bytes(buf) as usize;
+        let mut buf = [0; 8];
+        // n_nodes는 망가진 파일에서 터무니없이 클 수 있으니 미리 잡지 않는다.
+        let mut cords = Vec::new();
+        for _ in 0..n_nodes {
             // Create each inner `cord` with the constant `n_beakers` size
             let mut current_cord = Cord::with_capacity(n_beakers);
             for _ in 0..n_beakers {
                 // Read n_beakers elements
                 br.read_exact(&mut buf)?;
-                current_cord.push(u32::from_le_bytes(buf));
+                current_cord.push(Waters::from_le_bytes(buf));
             }
-            self.cords.push(current_cord);
+            cords.push(current_cord);
         }
+
+        // 가지가 없던 때의 파일이면 노드들이 한 줄기로 이어진다.
+        let links = Self::read_links(br, n_nodes)?
+            .unwrap_or_else(|| (0..n_nodes).map(|i| (i.checked_sub(1), 0)).collect());
+        *self = Self::from_parts(cords.into_iter().zip(links).collect(), current)?;
         Ok(())
     }
+
+    /// 노드마다 (cord, (부모, 고른 가지))에서 나무를 만든다. 부모는 자식보다 앞에 있어야 한다.
+    pub(crate) fn from_parts(parts: Vec<(Cord, Link)>, current: usize) -> Result<Self, String> {
+        let n_nodes = parts.len();
+        if current >= n_nodes {
+            return Err(format!("history step {current} is out of {n_nodes} nodes"));
+        }
+        let mut nodes: Vec<HistoryNode> = Vec::with_capacity(n_nodes);
+        for (i, (cord, (parent, branch))) in parts.into_iter().enumerate() {
+            if cord.len() != nodes.first().map_or(cord.len(), |root| root.cord.len()) {
+                return Err(format!("history node {i} has {} beakers", cord.len()));
+            }
+            let depth = match parent {
+                None if i == 0 => 0,
+                Some(parent) if parent < i => {
+                    nodes[parent].children.push(i);
+                    nodes[parent].depth + 1
+                }
+                _ => return Err(format!("history node {i} has a bad parent {parent:?}")),
+            };
+            let mut node = HistoryNode::new(cord, parent, depth);
+            node.branch = branch;
+            nodes.push(node);
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            if node.branch > 0 && node.branch >= node.children.len() {
+                return Err(format!("history node {i} has a bad branch {}", node.branch));
+            }
+        }
+        Ok(Self { nodes, current })
+    }
+
+    /// 루트가 아닌 노드마다 (노드의 번호, 부모의 cord, 노드의 cord)
+    pub(crate) fn edges(&self) -> impl Iterator<Item = (usize, &Cord, &Cord)> {
+        self.nodes.iter().enumerate().filter_map(|(i, node)| {
+            let parent = node.parent?;
+            Some((i, &self.nodes[parent].cord, &node.cord))
+        })
+    }
+
+    /// from_parts()의 반대
+    #[cfg(feature = "serde")]
+    pub(crate) fn parts(&self) -> impl Iterator<Item = (&Cord, Link)> {
+        self.nodes
+            .iter()
+            .map(|node| (&node.cord, (node.parent, node.branch)))
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// 노드마다 (부모, 고른 가지). 파일이 (부모, 고른 가지) 없이 끝나면 None
+    fn read_links<R: Read>(br: &mut R, n_nodes: usize) -> std::io::Result<Option<Vec<Link>>> {
+        let mut buf = [0; 4];
+        let mut links = Vec::with_capacity(n_nodes);
+        for i in 0..n_nodes {
+            match br.read_exact(&mut buf) {
+                Err(e) if i == 0 && e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                result => result?,
+            }
+            let parent = u32::from_le_bytes(buf);
+            br.read_exact(&mut buf)?;
+            let branch = u32::from_le_bytes(buf) as usize;
+            links.push(((parent != NO_PARENT).then_some(parent as usize), branch));
+        }
+        Ok(Some(links))
+    }
 }
@@ -0,0 +1,72 @@
+//! 솔버가 이미 찾은 답을 기억해 두었다가, 같은 상태를 다시 풀 때 탐색 없이 돌려준다.
+//! 힌트를 따라 붓거나 되돌리기를 하면 답을 아는 상태로 가는 일이 많다.
+//!
+//! 찾은 경로 위의 상태와 풀 수 없는 상태만 기억한다. 탐색 중에 만나기만 한 상태(g_score)는 처음 상태에서의
+//! 거리만 알고 목표까지의 거리는 모르며, 붓기는 되돌릴 수 없으므로 그 상태에서의 가장 짧은 답을 만들 수 없다.
+
+use std::collections::HashMap;
+
+use state::{
+    moves::{Move, moves_of_path, replay},
+    state::State,
+};
+
+/// 기억한 상태가 이만큼이 되면 모두 잊고 다시 모은다.
+pub const KNOWN_CAPACITY: usize = 1 << 16;
+
+/// 찾은 경로 위의 상태마다 그 상태부터 목표까지의 경로를 안다.
+/// 가장 짧은 경로의 뒷부분은 그 상태에서 가장 짧은 경로이다.
+#[derive(Debug, Default)]
+pub struct KnownAnswers {
+    paths: Vec<Vec<State>>,
+    // 상태 -> (paths의 번호, 경로 위의 위치). None이면 풀 수 없는 상태이다.
+    positions: HashMap<State, Option<(usize, usize)>>,
+}
+
+impl KnownAnswers {
+    /// 답을 아는 상태의 개수
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.positions.clear();
+    }
+
+    /// state에서 찾은 답을 기억한다. moves가 None이면 state는 풀 수 없다.
+    pub fn insert(&mut self, state: &State, moves: Option<&[Move]>) {
+        if self.positions.len() >= KNOWN_CAPACITY {
+            self.clear();
+        }
+        let Some(moves) = moves else {
+            self.positions.insert(state.clone(), None);
+            return;
+        };
+        let Ok(path) = replay(state, moves) else {
+            return;
+        };
+        let id = self.paths.len();
+        for (position, state) in path.iter().enumerate() {
+            self.positions
+                .entry(state.clone())
+                .or_insert(Some((id, position)));
+        }
+        self.paths.push(path);
+    }
+
+    /// state에서 목표까지의 붓기. 모르는 상태이면 None, 풀 수 없는 상태이면 Some(None)
+    pub fn get(&self, state: &State) -> Option<Option<Vec<Move>>> {
+        let Some((id, position)) = *self.positions.get(state)? else {
+            return Some(None);
+        };
+        // 기억한 상태와 비커의 순서나 색의 이름이 다를 수 있으므로 state에서 다시 적는다.
+        let mut path = vec![state.clone()];
+        path.extend_from_slice(&self.paths[id][position + 1..]);
+        moves_of_path(&path).map(Some)
+    }
+}
@@ -1,3 +1,10 @@
 pub mod puzzle;
 pub mod history;
-pub mod solver;
\ No newline at end of file
+pub mod save_file;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod slots;
+pub mod stats;
+pub mod known_answers;
+pub mod solver;
+pub mod generator;
\ No newline at end of file
@@ -1,25 +1,44 @@
 use std::{
     error::Error,
     io::{Read, Write},
+    time::Duration,
 };
 
-use state::{Cord, INFINITY_USIZE, beaker::Beaker, state::State};
+use state::{
+    Cord, INFINITY_USIZE, Waters, beaker::Beaker, config::PuzzleConfig, error::PourError,
+    level_code::LevelCode, moves::Move, notation::BEAKER_SEPARATOR, state::State,
+};
 
-use crate::{history::History, solver::Solver};
+use crate::{
+    history::History,
+    save_file::{LoadError, check_cord, check_hidden_mask, check_history},
+    solver::{Solver, Strategy},
+    stats::{GameResult, GameTracker},
+};
 
 #[derive(Debug, Default)]
 pub struct Puzzle {
     state: State,
     selected_beaker_index: Option<usize>,
+    // 선택한 비커에서 부으면 풀 수 없게 되는 비커들. 선택할 때 정한다.
+    dead_targets: Vec<usize>,
     // hint_src_beaker_index: Option<usize>,
     hint_dst_beaker_index: Option<usize>,
+    // 저장 파일에서 읽은 판은 코드를 알 수 없다.
+    level_code: Option<LevelCode>,
+    // 마지막으로 붓지 못한 이유. 다음 동작에서 지워진다.
+    pour_error: Option<PourError>,
+    // 이 판에서 성공한 붓기의 수. 되돌린 붓기도 센다.
+    n_pours: usize,
     history: History,
     solver: Solver,
+    // 힌트와 되돌리기의 수, 걸린 시간. 새 판에서 다시 센다.
+    tracker: GameTracker,
 }
 
 impl Puzzle {
-    pub fn empty_new(n_beakers: usize) -> Self {
-        let state = State::new(n_beakers);
+    pub fn empty_new(config: PuzzleConfig) -> Self {
+        let state = State::new(config);
         let history = History::new(state.cord());
         Self {
             state,
@@ -28,12 +47,27 @@ impl Puzzle {
         }
     }
 
-    pub fn random_new(n_beakers: usize) -> Self {
-        let mut state = State::new(n_beakers);
-        state.random_generate();
+    pub fn random_new(config: PuzzleConfig) -> Self {
+        let mut state = State::new(config);
+        let seed = state.random_generate();
+        let history = History::new(state.cord());
+        let mut puzzle = Self {
+            state,
+            level_code: Some(LevelCode::new(config, seed)),
+            history,
+            ..Default::default()
+        };
+        puzzle.solve();
+        puzzle
+    }
+
+    pub fn from_level_code(level_code: LevelCode) -> Self {
+        let mut state = State::new(level_code.config);
+        state.generate_with_seed(level_code.seed);
         let history = History::new(state.cord());
         let mut puzzle = Self {
             state,
+            level_code: Some(level_code),
             history,
             ..Default::default()
         };
@@ -41,9 +75,90 @@ impl Puzzle {
         puzzle
     }
 
+    /// 글자로 적은 판(`RRGG|GGRR|....`)에서 시작한다. 이 판은 레벨 코드가 없다.
+    pub fn from_state(state: State) -> Self {
+        let history = History::new(state.cord());
+        let mut puzzle = Self {
+            state,
+            history,
+            ..Default::default()
+        };
+        puzzle.solve();
+        puzzle
+    }
+
+    /// initial에서 moves를 차례로 붓고 step번째 붓기 다음으로 되돌린 판. 붓지 못하는 붓기가 있으면 그 이유
+    pub fn replay(initial: State, moves: &[Move], step: usize) -> Result<Self, String> {
+        if step > moves.len() {
+            return Err(format!("step {step} is beyond {} moves", moves.len()));
+        }
+        let mut state = initial;
+        let mut history = History::new(state.cord());
+        // 되돌려도 드러난 층은 다시 가리지 않으므로 step에서의 가려진 층을 기억해 둔다.
+        let mut mask = state.hidden_mask();
+        for (i, mv) in moves.iter().enumerate() {
+            let actual = state
+                .move_of(mv.from, mv.to)
+                .map_err(|e| format!("move {i} ({mv}): {e}"))?;
+            if actual.color != mv.color {
+                return Err(format!("move {i} ({mv}) pours {actual}"));
+            }
+            state
+                .apply(actual)
+                .map_err(|e| format!("move {i} ({mv}): {e}"))?;
+            history.push(state.cord());
+            if i + 1 == step {
+                mask = state.hidden_mask();
+            }
+        }
+        for _ in step..moves.len() {
+            history.undo();
+        }
+        state.apply_cord(history.current());
+        state.apply_hidden_mask(&mask);
+        let mut puzzle = Self {
+            state,
+            history,
+            ..Default::default()
+        };
+        puzzle.solve();
+        Ok(puzzle)
+    }
+
+    /// 레벨 코드 또는 글자로 적은 판을 읽는다. `|`가 있으면 판으로 본다.
+    pub fn from_code(code: &str) -> Result<Self, String> {
+        if code.contains(BEAKER_SEPARATOR) {
+            Ok(Self::from_state(code.parse()?))
+        } else {
+            Ok(Self::from_level_code(code.parse()?))
+        }
+    }
+
+    /// config를 바꾸어 새 판을 만든다. 비커의 개수는 그대로여야 한다.
+    pub fn reset_with(&mut self, config: PuzzleConfig) {
+        assert_eq!(config.n_beakers(), self.n_beakers());
+        self.state = State::new(config);
+        self.reset();
+    }
+
+    /// level_code의 판으로 새로 시작한다. 비커의 개수는 그대로여야 한다. See `PendingLevel`.
+    pub fn reset_with_level_code(&mut self, level_code: LevelCode) {
+        assert_eq!(level_code.config.n_beakers(), self.n_beakers());
+        self.state = State::new(level_code.config);
+        self.state.generate_with_seed(level_code.seed);
+        self.start(level_code);
+    }
+
     pub fn reset(&mut self) {
-        self.state.random_generate();
+        let seed = self.state.random_generate();
+        self.start(LevelCode::new(self.config(), seed));
+    }
+
+    fn start(&mut self, level_code: LevelCode) {
+        self.level_code = Some(level_code);
         self.selected_beaker_index = None;
+        self.n_pours = 0;
+        self.tracker = GameTracker::default();
         self.history.reset(self.state.cord());
         self.solve();
     }
@@ -52,6 +167,18 @@ impl Puzzle {
         self.state.n_beakers()
     }
 
+    pub fn config(&self) -> PuzzleConfig {
+        self.state.config()
+    }
+
+    pub fn level_code(&self) -> Option<LevelCode> {
+        self.level_code
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.state.capacity()
+    }
+
     pub fn selected_beaker(&self) -> Option<&Beaker> {
         match self.selected_beaker_index {
             Some(index) => Some(self.state.beaker(index)),
@@ -63,6 +190,23 @@ impl Puzzle {
         Some(index) == self.selected_beaker_index
     }
 
+    /// 선택한 비커에서 index로 부으면 목표에 닿을 수 없게 되면 true. See `State::dead_moves_from()`.
+    pub fn is_dead_target_index(&self, index: usize) -> bool {
+        self.selected_beaker_index.is_some() && self.dead_targets.contains(&index)
+    }
+
+    /// index를 선택하고 그 비커에서 부으면 안 되는 곳을 정한다. 가려진 층은 보이는 상태로 판단한다.
+    fn select(&mut self, index: usize) {
+        self.selected_beaker_index = Some(index);
+        self.dead_targets = self
+            .state
+            .visible_state()
+            .dead_moves_from(index)
+            .into_iter()
+            .map(|mv| mv.to)
+            .collect();
+    }
+
     // pub fn is_hint_src_beaker_index(&self, index: usize) -> bool {
     //     Some(index) == self.hint_src_beaker_index
     // }
@@ -78,15 +222,57 @@ impl Puzzle {
         self.history.step()
     }
 
+    /// 이 판에서 성공한 붓기의 수. step()과 달리 되돌려도 줄지 않는다.
+    pub fn n_pours(&self) -> usize {
+        self.n_pours
+    }
+
+    /// 지금 판의 cord. 가려진 층은 UNKNOWN_WATER이다.
+    pub fn visible_cord(&self) -> Cord {
+        self.state.visible_state().cord()
+    }
+
     pub fn is_completed(&self) -> bool {
         self.state.is_completed()
     }
 
+    /// history의 처음 판. 아직 드러나지 않은 층은 지금처럼 가려 둔다.
+    pub fn initial_state(&self) -> State {
+        let mut state = State::new(self.config());
+        state.apply_cord(self.history.root());
+        state.apply_hidden_mask(&self.state.hidden_mask());
+        state
+    }
+
+    /// 처음 판에서 지금 단계를 지나 redo로 갈 수 있는 끝까지의 붓기들. See `History.main_line()`.
+    /// history의 이웃한 두 판이 붓기 한 번으로 이어지지 않으면 None
+    pub fn main_line_moves(&self) -> Option<Vec<Move>> {
+        let line = self.history.main_line();
+        let mut state = self.initial_state();
+        let mut moves = Vec::with_capacity(line.len() - 1);
+        for next in &line[1..] {
+            let mv = state.move_to_cord(next)?;
+            state.apply(mv).ok()?;
+            moves.push(mv);
+        }
+        Some(moves)
+    }
+
     pub fn pouring_results_different_state(&self) -> bool {
         self.state.pour_results_different_state()
     }
 
+    /// 지금 상태에서 목표에 닿을 수 없음이 증명되었으면 true. See `State::is_dead()`.
+    pub fn is_dead_end(&self) -> bool {
+        self.solver.dead_end
+    }
+
+    pub fn pour_error(&self) -> Option<PourError> {
+        self.pour_error
+    }
+
     pub fn on_right_click(&mut self, clicked_beaker_index: Option<usize>) {
+        self.pour_error = None;
         // 비커가 아닌 빈 공간을 클릭했으면 선택을 해제한다.
         let Some(clicked_index) = clicked_beaker_index else {
             self.selected_beaker_index = None;
@@ -102,10 +288,14 @@ impl Puzzle {
                 self.selected_beaker_index = None;
             } else {
                 // 선택된 비커에서 클릭한 비커로 물을 붓는다.
-                if self.state.pour(selected_index, clicked_index) {
-                    // 성공적으로 부었으면 현재 상태를 history에 저장한다.
-                    self.history.push(self.state.cord());
-                    self.solve();
+                match self.state.pour(selected_index, clicked_index) {
+                    Ok(_) => {
+                        // 성공적으로 부었으면 현재 상태를 history에 저장한다.
+                        self.history.push(self.state.cord());
+                        self.n_pours += 1;
+                        self.solve();
+                    }
+                    Err(e) => self.pour_error = Some(e),
                 }
                 // 붓기 성공/실패와 관계없이 선택을 해제한다.
                 self.selected_beaker_index = None;
@@ -113,8 +303,10 @@ impl Puzzle {
         } else {
             // 선택된 비커가 없는 경우
             // 클릭한 비커가 비어 있지 않으면 선택한다.
-            if !self.state.beaker(clicked_index).is_empty() {
-                self.selected_beaker_index = Some(clicked_index);
+            if self.state.beaker(clicked_index).is_empty() {
+                self.pour_error = Some(PourError::SourceEmpty);
+            } else {
+                self.select(clicked_index);
             }
         }
     }
@@ -122,25 +314,55 @@ impl Puzzle {
     pub fn on_left_click(&mut self, clicked_beaker_index: usize) {
         self.selected_beaker_index = None;
         self.hint_dst_beaker_index = None;
-        if let Some(target) = self.state.pourable_beaker_index(clicked_beaker_index) {
-            self.state.pour(clicked_beaker_index, target);
-            self.history.push(self.state.cord());
-            self.solve();
+        self.pour_error = None;
+        // 가려진 층의 색으로 대상을 고르지 않도록 보이는 상태에서 고른다.
+        let target = self
+            .state
+            .visible_state()
+            .pourable_beaker_index(clicked_beaker_index);
+        let result = match target {
+            Some(target) => self.state.pour(clicked_beaker_index, target),
+            None => Err(self.left_click_error(clicked_beaker_index)),
+        };
+        match result {
+            Ok(_) => {
+                self.history.push(self.state.cord());
+                self.n_pours += 1;
+                self.solve();
+            }
+            Err(e) => self.pour_error = Some(e),
+        }
+    }
+
+    /// 왼쪽 클릭한 비커에서 부을 곳이 없는 이유. 색이 맞지 않는 비커가 있으면 그것을, 없으면 TargetFull
+    fn left_click_error(&self, index: usize) -> PourError {
+        if self.state.beaker(index).is_empty() {
+            return PourError::SourceEmpty;
         }
+        (0..self.n_beakers())
+            .filter(|&j| j != index)
+            .filter_map(|j| self.state.check_pour(index, j).err())
+            .find(|e| *e != PourError::TargetFull)
+            .unwrap_or(PourError::TargetFull)
     }
 
     pub fn undo(&mut self) {
         if let Some(cord) = self.history.undo() {
             self.state.apply_cord(cord);
+            self.tracker.n_undos += 1;
             self.solve();
         }
     }
 
     pub fn undo_all(&mut self) {
+        if self.step() > 0 {
+            self.tracker.n_undos += 1;
+        }
         self.state.apply_cord(self.history.undo_all());
         self.solve();
     }
 
+    /// 고른 가지로 한 걸음 다시 간다. See `select_redo_branch()`.
     pub fn redo(&mut self) {
         if let Some(cord) = self.history.redo() {
             self.state.apply_cord(cord);
@@ -148,36 +370,101 @@ impl Puzzle {
         }
     }
 
+    /// 지금 상태에서 redo로 갈 수 있는 가지의 개수. 되돌린 다음 다른 붓기를 하면 가지가 늘어난다.
+    pub fn n_redo_branches(&self) -> usize {
+        self.history.n_branches()
+    }
+
+    /// redo가 갈 가지. 처음에는 마지막으로 지나간 가지이다.
+    pub fn redo_branch(&self) -> usize {
+        self.history.branch()
+    }
+
+    pub fn select_redo_branch(&mut self, branch: usize) {
+        self.history.select_branch(branch);
+    }
+
+    /// redo가 갈 가지를 다음(forward) 또는 앞의 가지로 돌려 가며 바꾼다.
+    pub fn cycle_redo_branch(&mut self, forward: bool) {
+        let n_branches = self.n_redo_branches();
+        if n_branches > 1 {
+            let offset = if forward { 1 } else { n_branches - 1 };
+            self.select_redo_branch((self.redo_branch() + offset) % n_branches);
+        }
+    }
+
+    /// 가지 탐색기에 보일 글. 가지가 둘 이상일 때에만 "Branch 2/3"
+    pub fn redo_branch_text(&self) -> String {
+        match self.n_redo_branches() {
+            0 | 1 => "".to_owned(),
+            n => format!("Branch {}/{n}", self.redo_branch() + 1),
+        }
+    }
+
     pub fn solve(&mut self) {
         // self.hint_src_beaker_index = None;
         self.selected_beaker_index = None;
         self.hint_dst_beaker_index = None;
-        self.solver.solve(&self.state);
+        self.pour_error = None;
+        self.solver.solve(&self.state.visible_state());
+        self.note_optimal();
+    }
+
+    pub fn solver_strategy(&self) -> Strategy {
+        self.solver.strategy()
+    }
+
+    /// 힌트를 찾는 방법을 바꾸고 지금 상태에서 다시 찾는다.
+    pub fn set_solver_strategy(&mut self, strategy: Strategy) {
+        self.solver.set_strategy(strategy);
+        self.solve();
     }
 
     pub fn check_solver(&mut self) {
         self.solver.check();
+        self.note_optimal();
+    }
+
+    /// 처음 판의 답이 나오면 그 길이를 가장 짧은 수로 기억한다.
+    /// 가려진 층이 있거나, 가장 짧은 경로를 보장하지 않는 방법(`Strategy::finds_shortest()`)으로 찾았거나,
+    /// 한도에 닿은 답은 가장 짧다고 할 수 없다.
+    fn note_optimal(&mut self) {
+        if self.tracker.optimal.is_none()
+            && self.step() == 0
+            && self.state.n_hidden() == 0
+            && self.solver.strategy().finds_shortest()
+            && !self.solver.is_solving()
+            && !self.solver.partial
+            && let Some(step) = self.solver.remaining_step()
+            && step != INFINITY_USIZE
+        {
+            self.tracker.optimal = Some(step);
+        }
+    }
+
+    /// 솔버가 알려 주는 다음 붓기
+    pub fn hint(&self) -> Option<Move> {
+        self.solver.hint()
     }
 
     pub fn reset_solver_hint(&mut self) {
-        if let Some(hint_state) = self.solver.hint() {
-            self.selected_beaker_index = None;
-            for (i, hint_beaker) in hint_state.beakers.iter().enumerate() {
-                let beaker = self.state.beaker(i);
-                if hint_beaker.n_waters < beaker.n_waters {
-                    // self.hint_src_beaker_index = Some(i);
-                    self.selected_beaker_index = Some(i);
-                } else if hint_beaker.n_waters > beaker.n_waters {
-                    self.hint_dst_beaker_index = Some(i);
-                }
-            }
+        if let Some(mv) = self.hint() {
+            self.tracker.count_hint(self.state.cord());
+            // self.hint_src_beaker_index = Some(mv.from);
+            self.select(mv.from);
+            self.hint_dst_beaker_index = Some(mv.to);
         }
     }
 
     pub fn apply_solver_hint(&mut self) {
-        if let Some(next_state) = self.solver.hint() {
-            self.state = next_state.clone();
+        // 솔버의 상태에는 가려진 층이 UNKNOWN_WATER로 들어 있으므로 그대로 쓰지 않고 실제 상태에 붓는다.
+        let cord = self.state.cord();
+        if let Some(mv) = self.hint()
+            && self.state.apply(mv).is_ok()
+        {
+            self.tracker.count_hint(cord);
             self.history.push(self.state.cord());
+            self.n_pours += 1;
             self.selected_beaker_index = None;
             self.solve();
         }
@@ -185,6 +472,7 @@ impl Puzzle {
 
     pub fn solver_emoticon(&self) -> String {
         match self.solver.remaining_step() {
+            Some(_) if self.solver.partial => ":-/".to_owned(),
             Some(step) => {
                 if step == INFINITY_USIZE {
                     ":-(".to_owned()
@@ -198,9 +486,15 @@ impl Puzzle {
 
     pub fn solver_remaining_step(&self) -> String {
         match self.solver.remaining_step() {
+            // 한도에 닿아 찾은 도중의 경로이므로 끝까지 몇 번인지 모른다.
+            Some(INFINITY_USIZE) if self.solver.partial => "?".to_owned(),
+            Some(step) if self.solver.partial => format!("{step}?"),
             Some(step) => {
                 if step == INFINITY_USIZE {
                     "\u{221e}".to_owned()
+                } else if self.state.n_hidden() > 0 {
+                    // 가려진 층이 드러날 때까지의 붓기 수이므로 끝까지는 더 걸릴 수 있다.
+                    format!("{step}+")
                 } else {
                     step.to_string()
                 }
@@ -209,25 +503,87 @@ impl Puzzle {
         }
     }
 
+    /// 이 판을 열고 나서 지난 시간
+    pub fn elapsed(&self) -> Duration {
+        self.tracker.elapsed()
+    }
+
+    /// 다 풀었으면 그 기록을 한 번만 돌려준다. 불러온 판을 붓지 않고 끝난 상태로 두면 기록하지 않는다.
+    pub fn finish_game(&mut self) -> Option<GameResult> {
+        if self.tracker.recorded || self.n_pours == 0 || !self.is_completed() {
+            return None;
+        }
+        self.tracker.recorded = true;
+        Some(self.tracker.result(self.config(), true, self.step()))
+    }
+
+    /// 풀지 않고 다른 판으로 넘어갈 때의 기록. 한 번도 붓지 않았으면 기록하지 않는다.
+    pub fn give_up(&mut self) -> Option<GameResult> {
+        if self.tracker.recorded || self.n_pours == 0 {
+            return None;
+        }
+        self.tracker.recorded = true;
+        Some(self.tracker.result(self.config(), false, self.step()))
+    }
+
+    /// 찾는 중이면 "Thinking..."과 지금까지의 탐색 통계, 끝났으면 마지막 탐색 통계
+    pub fn solver_stats(&self) -> String {
+        let stats = self.solver.stats.map(|stats| stats.to_string());
+        if self.solver.is_solving() {
+            match stats {
+                Some(stats) => format!("Thinking... {stats}"),
+                None => "Thinking...".to_owned(),
+            }
+        } else {
+            stats.unwrap_or_default()
+        }
+    }
+
     pub fn save<W: Write>(&self, bw: &mut W) -> Result<(), Box<dyn Error>> {
         for x in self.state.cord() {
             bw.write_all(&x.to_le_bytes())?;
         }
+        bw.write_all(&self.state.hidden_mask())?;
         self.history.save(bw)?;
         Ok(())
     }
 
-    pub fn load<R: Read>(&mut self, br: &mut R) -> Result<(), Box<dyn Error>> {
+    /// 판, 가려진 층, history를 읽고 솔버를 돌린다. 읽은 판과 history의 판들이 설정에 맞지 않으면 오류
+    pub fn load<R: Read>(&mut self, br: &mut R) -> Result<(), LoadError> {
+        self.read(br)?;
+        self.solve();
+        Ok(())
+    }
+
+    /// load()와 같지만 솔버를 돌리지 않는다.
+    pub(crate) fn read<R: Read>(&mut self, br: &mut R) -> Result<(), LoadError> {
+        let config = self.config();
         let n_beakers = self.n_beakers();
         let mut cord = Cord::with_capacity(n_beakers);
-        let mut buf = [0; 4];
+        let mut buf = [0; 8];
         for _ in 0..n_beakers {
             br.read_exact(&mut buf)?;
-            cord.push(u32::from_le_bytes(buf));
+            cord.push(Waters::from_le_bytes(buf));
         }
+        check_cord(&cord, config)?;
+        let mut mask = vec![0; n_beakers];
+        br.read_exact(&mut mask)?;
+        check_hidden_mask(&cord, &mask)?;
+
+        let mut history = History::default();
+        history
+            .load(br, n_beakers)
+            .map_err(|e| LoadError::from_boxed(e, LoadError::InvalidHistory))?;
+        check_history(&history, config)?;
+        if *history.current() != cord {
+            return Err(LoadError::InvalidHistory(
+                "the current node is not the board".to_string(),
+            ));
+        }
+
         self.state.apply_cord(&cord);
-        self.history.load(br, n_beakers)?;
-        self.solve();
+        self.state.apply_hidden_mask(&mask);
+        self.history = history;
         Ok(())
     }
 }
@@ -0,0 +1,435 @@
+//! 저장 파일(save.wp)의 형식.
+//!
+//! 머리말: MAGIC, VERSION, 본문의 길이, 본문의 CRC-32 (모두 little-endian u32)
+//! 본문: 줄의 개수, 칸의 개수, `PuzzleConfig::save()`, 비커의 개수, `Puzzle::save()`
+//!
+//! 머리말이 없는 가장 처음의 파일도 읽는다. 그 파일은 설정 없이 u32 cord를 적었고,
+//! 용량 4, 빈 비커 2개, 줄 × 칸 개의 비커이다.
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, ErrorKind, Read, Write},
+};
+
+use state::{
+    Cord, Water, Waters,
+    beaker::{NO_WATER, UNKNOWN_WATER},
+    config::PuzzleConfig,
+    notation::water_char,
+    state::State,
+};
+
+use crate::{history::History, puzzle::Puzzle};
+
+pub const MAGIC: &[u8; 4] = b"WPZL";
+pub const VERSION: u32 = 1;
+
+/// 처음 파일의 비커 용량과 빈 비커의 개수
+const BASELINE_CAPACITY: usize = 4;
+const BASELINE_N_EMPTY: usize = 2;
+
+/// cord가 올바른 판이 아닌 이유
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CordError {
+    /// 비커에 용량보다 많은 층이 있다.
+    Overfilled { beaker: usize },
+    /// 빈 칸 위에 물이 있다.
+    WaterAboveBlank { beaker: usize },
+    /// 설정의 색 범위에 없는 색이다.
+    InvalidColor { beaker: usize, water: Water },
+    /// 색마다 용량만큼 있어야 한다.
+    ColorCount {
+        water: Water,
+        count: usize,
+        expected: usize,
+    },
+    /// 가려진 층의 표시가 물이 없는 층에 있다.
+    HiddenMask { beaker: usize },
+}
+
+impl fmt::Display for CordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overfilled { beaker } => write!(f, "beaker {beaker} holds too many layers"),
+            Self::WaterAboveBlank { beaker } => {
+                write!(f, "beaker {beaker} has water above a blank")
+            }
+            Self::InvalidColor { beaker, water } => {
+                write!(f, "beaker {beaker} has an unknown colour ({water})")
+            }
+            Self::ColorCount {
+                water,
+                count,
+                expected,
+            } => write!(
+                f,
+                "colour {} appears {count} times, expected {expected}",
+                water_char(*water).map_or(format!("({water})"), String::from)
+            ),
+            Self::HiddenMask { beaker } => {
+                write!(f, "beaker {beaker} hides a layer it does not have")
+            }
+        }
+    }
+}
+
+impl Error for CordError {}
+
+/// 저장 파일을 읽지 못한 이유
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// 머리말이 없고 처음 파일의 형식도 아니다.
+    NotASaveFile,
+    /// 파일이 중간에 끝났다.
+    Truncated,
+    /// 읽을 것이 끝났는데 바이트가 남았다.
+    TrailingBytes(usize),
+    /// 이 프로그램보다 새 버전의 파일이다.
+    UnsupportedVersion(u32),
+    ChecksumMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    InvalidConfig(String),
+    /// 판이 줄 × 칸에 들어가지 않는다.
+    DoesNotFit {
+        n_beakers: usize,
+        n_rows: usize,
+        n_cols: usize,
+    },
+    BeakerCount {
+        expected: usize,
+        found: usize,
+    },
+    InvalidBoard(CordError),
+    InvalidHistory(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "cannot read the save file: {e}"),
+            Self::NotASaveFile => write!(f, "not a save file"),
+            Self::Truncated => write!(f, "the save file is cut short"),
+            Self::TrailingBytes(n) => {
+                write!(f, "the save file has {n} unexpected bytes at the end")
+            }
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "save file version {version} is not supported (expected {VERSION})"
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "the save file is corrupt (checksum {actual:08x}, expected {expected:08x})"
+            ),
+            Self::InvalidConfig(e) => write!(f, "invalid puzzle config: {e}"),
+            Self::DoesNotFit {
+                n_beakers,
+                n_rows,
+                n_cols,
+            } => write!(f, "{n_beakers} beakers do not fit in {n_rows}x{n_cols}"),
+            Self::BeakerCount { expected, found } => {
+                write!(f, "the board has {found} beakers, expected {expected}")
+            }
+            Self::InvalidBoard(e) => write!(f, "invalid board: {e}"),
+            Self::InvalidHistory(e) => write!(f, "invalid undo history: {e}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::InvalidBoard(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            Self::Truncated
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+impl From<CordError> for LoadError {
+    fn from(e: CordError) -> Self {
+        Self::InvalidBoard(e)
+    }
+}
+
+impl LoadError {
+    /// Box<dyn Error>를 돌려주는 읽기 함수의 오류. 파일이 끝나서 난 오류는 Truncated로 바꾼다.
+    pub(crate) fn from_boxed(e: Box<dyn Error>, wrap: fn(String) -> Self) -> Self {
+        match e.downcast::<io::Error>() {
+            Ok(e) => Self::from(*e),
+            Err(e) => wrap(e.to_string()),
+        }
+    }
+}
+
+/// n_rows × n_cols 칸에 놓인 puzzle을 머리말을 붙여 저장한다.
+pub fn save<W: Write>(
+    bw: &mut W,
+    n_rows: usize,
+    n_cols: usize,
+    puzzle: &Puzzle,
+) -> Result<(), Box<dyn Error>> {
+    let mut body = Vec::new();
+    body.write_all(&(n_rows as u32).to_le_bytes())?;
+    body.write_all(&(n_cols as u32).to_le_bytes())?;
+    puzzle.config().save(&mut body)?;
+    body.write_all(&(puzzle.n_beakers() as u32).to_le_bytes())?;
+    puzzle.save(&mut body)?;
+
+    bw.write_all(MAGIC)?;
+    bw.write_all(&VERSION.to_le_bytes())?;
+    bw.write_all(&(body.len() as u32).to_le_bytes())?;
+    bw.write_all(&crc32(&body).to_le_bytes())?;
+    bw.write_all(&body)?;
+    Ok(())
+}
+
+/// 저장 파일을 읽어 (줄의 개수, 칸의 개수, puzzle)을 돌려준다. 머리말이 없는 예전 파일도 읽는다.
+pub fn load<R: Read>(br: &mut R) -> Result<(usize, usize, Puzzle), LoadError> {
+    let (n_rows, n_cols, mut puzzle) = read(br)?;
+    puzzle.solve();
+    Ok((n_rows, n_cols, puzzle))
+}
+
+/// 슬롯 목록에 보여 줄 저장된 판의 요약
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preview {
+    pub n_rows: usize,
+    pub n_cols: usize,
+    pub config: PuzzleConfig,
+    /// 지금 판. 가려진 층은 UNKNOWN_WATER이다.
+    pub cord: Cord,
+    pub step: usize,
+    pub is_completed: bool,
+}
+
+/// 솔버를 돌리지 않고 저장 파일의 요약만 읽는다.
+pub fn load_preview<R: Read>(br: &mut R) -> Result<Preview, LoadError> {
+    let (n_rows, n_cols, puzzle) = read(br)?;
+    Ok(Preview {
+        n_rows,
+        n_cols,
+        config: puzzle.config(),
+        cord: puzzle.visible_cord(),
+        step: puzzle.step(),
+        is_completed: puzzle.is_completed(),
+    })
+}
+
+fn read<R: Read>(br: &mut R) -> Result<(usize, usize, Puzzle), LoadError> {
+    let mut bytes = Vec::new();
+    br.read_to_end(&mut bytes)?;
+    match bytes.strip_prefix(MAGIC) {
+        Some(rest) => load_versioned(rest),
+        None => load_legacy(&bytes),
+    }
+}
+
+fn load_versioned(mut bytes: &[u8]) -> Result<(usize, usize, Puzzle), LoadError> {
+    let version = read_u32(&mut bytes)?;
+    if version != VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+    let len = read_u32(&mut bytes)? as usize;
+    let expected = read_u32(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(LoadError::Truncated);
+    }
+    if bytes.len() > len {
+        return Err(LoadError::TrailingBytes(bytes.len() - len));
+    }
+    let actual = crc32(bytes);
+    if actual != expected {
+        return Err(LoadError::ChecksumMismatch { expected, actual });
+    }
+    read_body(&mut bytes)
+}
+
+/// 머리말이 없는 파일. 처음 파일을 지금의 본문으로 바꾸어 읽는다.
+fn load_legacy(bytes: &[u8]) -> Result<(usize, usize, Puzzle), LoadError> {
+    let body = migrate_baseline(bytes).ok_or(LoadError::NotASaveFile)?;
+    read_body(&mut body.as_slice())
+}
+
+fn read_body(bytes: &mut &[u8]) -> Result<(usize, usize, Puzzle), LoadError> {
+    let n_rows = read_u32(bytes)? as usize;
+    let n_cols = read_u32(bytes)? as usize;
+    let config = PuzzleConfig::load(bytes)
+        .map_err(|e| LoadError::from_boxed(e, LoadError::InvalidConfig))?;
+    let n_beakers = config.n_beakers();
+    let found = read_u32(bytes)? as usize;
+    if found != n_beakers {
+        return Err(LoadError::BeakerCount {
+            expected: n_beakers,
+            found,
+        });
+    }
+    if n_beakers > n_rows.saturating_mul(n_cols) {
+        return Err(LoadError::DoesNotFit {
+            n_beakers,
+            n_rows,
+            n_cols,
+        });
+    }
+    // 판을 만들기 전에 cord와 가려진 층이 들어갈 만큼 남았는지 본다.
+    if bytes.len() < 9 * n_beakers {
+        return Err(LoadError::Truncated);
+    }
+    let mut puzzle = Puzzle::empty_new(config);
+    puzzle.read(bytes)?;
+    if !bytes.is_empty() {
+        return Err(LoadError::TrailingBytes(bytes.len()));
+    }
+    Ok((n_rows, n_cols, puzzle))
+}
+
+/// 처음 파일(줄, 칸, u32 cord, u32 cord의 한 줄기 history)을 지금의 본문으로 바꾼다.
+/// 길이가 그 형식과 맞지 않으면 None
+fn migrate_baseline(mut bytes: &[u8]) -> Option<Vec<u8>> {
+    let n_rows = read_u32(&mut bytes).ok()?;
+    let n_cols = read_u32(&mut bytes).ok()?;
+    let n_beakers = (n_rows as usize).checked_mul(n_cols as usize)?;
+    if n_beakers <= BASELINE_N_EMPTY {
+        return None;
+    }
+    let cord_len = 4 * n_beakers;
+    let n_cords = u32::from_le_bytes(bytes.get(cord_len..cord_len + 4)?.try_into().ok()?);
+    if bytes.len() != cord_len + 8 + cord_len.checked_mul(n_cords as usize)? {
+        return None;
+    }
+
+    let config = PuzzleConfig::new(
+        n_beakers - BASELINE_N_EMPTY,
+        BASELINE_N_EMPTY,
+        BASELINE_CAPACITY,
+    );
+    let mut body = Vec::new();
+    body.extend_from_slice(&n_rows.to_le_bytes());
+    body.extend_from_slice(&n_cols.to_le_bytes());
+    config.save(&mut body).ok()?;
+    body.extend_from_slice(&(n_beakers as u32).to_le_bytes());
+    let widen = |body: &mut Vec<u8>, cord: &[u8]| {
+        for waters in cord.chunks_exact(4) {
+            let waters = u32::from_le_bytes(waters.try_into().unwrap()) as Waters;
+            body.extend_from_slice(&waters.to_le_bytes());
+        }
+    };
+    widen(&mut body, &bytes[..cord_len]);
+    // 처음 파일에는 가려진 층이 없다.
+    body.extend(std::iter::repeat_n(0, n_beakers));
+    // history의 (cord의 개수, 지금 위치)
+    body.extend_from_slice(&bytes[cord_len..cord_len + 8]);
+    for cord in bytes[cord_len + 8..].chunks_exact(cord_len) {
+        widen(&mut body, cord);
+    }
+    Some(body)
+}
+
+/// cord가 config의 올바른 판인지 본다. 색마다 capacity만큼 있고 빈 칸 위에 물이 없어야 한다.
+pub fn check_cord(cord: &Cord, config: PuzzleConfig) -> Result<(), CordError> {
+    let capacity = config.capacity;
+    let mut counts = vec![0; config.n_colors + 1];
+    for (beaker, &waters) in cord.iter().enumerate() {
+        let layers = (0..8).map(|order| ((waters >> (8 * order)) & 0xFF) as Water);
+        let mut blank = false;
+        for (order, water) in layers.enumerate() {
+            if water == NO_WATER {
+                blank = true;
+            } else if order >= capacity {
+                return Err(CordError::Overfilled { beaker });
+            } else if blank {
+                return Err(CordError::WaterAboveBlank { beaker });
+            } else if water == UNKNOWN_WATER || water as usize > config.n_colors {
+                return Err(CordError::InvalidColor { beaker, water });
+            } else {
+                counts[water as usize] += 1;
+            }
+        }
+    }
+    match counts
+        .iter()
+        .enumerate()
+        .skip(1)
+        .find(|&(_, &count)| count != capacity)
+    {
+        Some((water, &count)) => Err(CordError::ColorCount {
+            water: water as Water,
+            count,
+            expected: capacity,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// history의 판마다 check_cord()로 보고, 노드마다 부모에서 config의 규칙으로 한 번 부은 판인지 본다.
+pub fn check_history(history: &History, config: PuzzleConfig) -> Result<(), LoadError> {
+    for (i, cord) in history.cords().enumerate() {
+        check_cord(cord, config)
+            .map_err(|e| LoadError::InvalidHistory(format!("node {i}: {e}")))?;
+    }
+    for (i, parent, cord) in history.edges() {
+        let mut state = State::new(config.with_mystery(false));
+        state.apply_cord(parent);
+        let n = state.n_beakers();
+        let is_one_pour = (0..n)
+            .flat_map(|from| (0..n).map(move |to| (from, to)))
+            .any(|(from, to)| {
+                let mut poured = state.clone();
+                poured.pour(from, to).is_ok() && poured.cord() == *cord
+            });
+        if !is_one_pour {
+            return Err(LoadError::InvalidHistory(format!(
+                "node {i} is not one pour from its parent"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 가려진 층의 표시가 물이 있는 층에만 있는지 본다.
+pub fn check_hidden_mask(cord: &Cord, mask: &[u8]) -> Result<(), CordError> {
+    for (beaker, (&waters, &hidden)) in cord.iter().zip(mask).enumerate() {
+        let n_waters = (0..8)
+            .take_while(|order| (waters >> (8 * order)) & 0xFF != NO_WATER as Waters)
+            .count();
+        if (hidden as u32) >> n_waters != 0 {
+            return Err(CordError::HiddenMask { beaker });
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn read_u32(bytes: &mut &[u8]) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    bytes.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// CRC-32 (IEEE 802.3)
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
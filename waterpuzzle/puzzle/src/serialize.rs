@@ -0,0 +1,110 @@
+//! `serde` 기능을 켜면 쓰는 Serialize, Deserialize와 JSON 내보내기.
+//! Puzzle은 처음 판, 붓기들, 지금 단계로 적고 읽을 때 처음부터 다시 붓는다. 설정은 처음 판에 들어 있다.
+//! 예: `{"initial": {"config": {...}, "beakers": [...]}, "moves": [{"from": 0, "to": 4, ...}], "step": 1}`
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _, ser::Error as _};
+use state::{
+    Cord,
+    moves::Move,
+    notation::{cord_from_text, cord_to_text},
+    state::State,
+};
+
+use crate::{
+    history::{History, Link},
+    puzzle::Puzzle,
+    save_file::check_cord,
+};
+
+#[derive(Serialize, Deserialize)]
+struct NodeRecord {
+    cord: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<usize>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    branch: usize,
+}
+
+fn is_zero(branch: &usize) -> bool {
+    *branch == 0
+}
+
+/// 노드는 만든 순서대로 적고, 부모는 언제나 자식보다 앞에 있다.
+#[derive(Serialize, Deserialize)]
+struct HistoryRecord {
+    nodes: Vec<NodeRecord>,
+    current: usize,
+}
+
+impl Serialize for History {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let nodes = self
+            .parts()
+            .map(|(cord, (parent, branch))| NodeRecord {
+                cord: cord_to_text(cord),
+                parent,
+                branch,
+            })
+            .collect();
+        HistoryRecord {
+            nodes,
+            current: self.current_index(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for History {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = HistoryRecord::deserialize(deserializer)?;
+        let mut parts: Vec<(Cord, Link)> = Vec::with_capacity(record.nodes.len());
+        for node in record.nodes {
+            let cord = cord_from_text(&node.cord).map_err(D::Error::custom)?;
+            parts.push((cord, (node.parent, node.branch)));
+        }
+        Self::from_parts(parts, record.current).map_err(D::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GameRecord {
+    initial: State,
+    moves: Vec<Move>,
+    step: usize,
+}
+
+impl Serialize for Puzzle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let moves = self
+            .main_line_moves()
+            .ok_or_else(|| S::Error::custom("the undo history does not follow the pours"))?;
+        GameRecord {
+            initial: self.initial_state(),
+            moves,
+            step: self.step(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Puzzle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = GameRecord::deserialize(deserializer)?;
+        check_cord(&record.initial.cord(), record.initial.config())
+            .map_err(|e| D::Error::custom(format!("invalid initial board: {e}")))?;
+        Self::replay(record.initial, &record.moves, record.step).map_err(D::Error::custom)
+    }
+}
+
+impl Puzzle {
+    /// 처음 판, 붓기들, 지금 단계, 설정을 사람이 읽을 수 있는 JSON으로 적는다.
+    /// 되돌린 다음 고르지 않은 가지는 적지 않는다.
+    pub fn export_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// export_json()으로 적은 게임을 읽는다. 오류는 JSON의 줄과 칸을 알려 준다.
+    pub fn import_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
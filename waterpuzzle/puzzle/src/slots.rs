@@ -0,0 +1,172 @@
+//! 이름을 붙인 저장 슬롯. 슬롯마다 폴더 안의 `<이름>.wp` 파일 하나이고 형식은 `save_file`과 같다.
+//! 저장은 임시 파일에 쓴 다음 이름을 바꾸므로, 쓰는 도중에 프로그램이 죽어도 이전 슬롯이 남는다.
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    puzzle::Puzzle,
+    save_file::{self, LoadError, Preview},
+};
+
+pub const SLOT_EXTENSION: &str = "wp";
+/// 자동 저장과 프로그램을 닫을 때 쓰는 슬롯
+pub const AUTOSAVE_SLOT: &str = "autosave";
+/// 몇 번 부을 때마다 자동 저장하는지
+pub const DEFAULT_AUTOSAVE_INTERVAL: usize = 5;
+
+/// 슬롯 목록의 한 줄
+#[derive(Debug)]
+pub struct SlotInfo {
+    pub name: String,
+    /// 마지막으로 저장한 때
+    pub modified: SystemTime,
+    /// 읽지 못한 슬롯은 그 이유
+    pub preview: Result<Preview, LoadError>,
+}
+
+#[derive(Debug)]
+pub struct SlotManager {
+    dir: PathBuf,
+    // 0이면 자동 저장하지 않는다.
+    autosave_interval: usize,
+    // 마지막으로 자동 저장했을 때의 Puzzle::n_pours()
+    autosaved_pours: usize,
+}
+
+impl SlotManager {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            autosave_interval: DEFAULT_AUTOSAVE_INTERVAL,
+            autosaved_pours: 0,
+        }
+    }
+
+    pub fn with_autosave_interval(mut self, autosave_interval: usize) -> Self {
+        self.autosave_interval = autosave_interval;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name).with_extension(SLOT_EXTENSION)
+    }
+
+    /// 최근에 저장한 슬롯부터. 폴더가 없으면 빈 목록
+    pub fn list(&self) -> io::Result<Vec<SlotInfo>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut slots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != SLOT_EXTENSION)
+            {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let modified = fs::metadata(&path)?.modified()?;
+            let preview = File::open(&path)
+                .map_err(LoadError::from)
+                .and_then(|file| save_file::load_preview(&mut BufReader::new(file)));
+            slots.push(SlotInfo {
+                name: name.to_string(),
+                modified,
+                preview,
+            });
+        }
+        slots.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.name.cmp(&b.name)));
+        Ok(slots)
+    }
+
+    pub fn save(
+        &self,
+        name: &str,
+        n_rows: usize,
+        n_cols: usize,
+        puzzle: &Puzzle,
+    ) -> Result<(), Box<dyn Error>> {
+        check_slot_name(name)?;
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(name);
+        let temp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        save_file::save(&mut writer, n_rows, n_cols, puzzle)?;
+        writer.into_inner()?.sync_all()?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    pub fn load(&self, name: &str) -> Result<(usize, usize, Puzzle), LoadError> {
+        let file = File::open(self.path(name))?;
+        save_file::load(&mut BufReader::new(file))
+    }
+
+    pub fn delete(&self, name: &str) -> io::Result<()> {
+        fs::remove_file(self.path(name))
+    }
+
+    /// 지난 자동 저장 다음으로 autosave_interval 번 부었으면 AUTOSAVE_SLOT에 저장한다. 저장했으면 true
+    /// 새 판이 시작되어 붓기의 수가 줄면 다시 센다.
+    pub fn autosave(
+        &mut self,
+        n_rows: usize,
+        n_cols: usize,
+        puzzle: &Puzzle,
+    ) -> Result<bool, Box<dyn Error>> {
+        let n_pours = puzzle.n_pours();
+        if n_pours < self.autosaved_pours {
+            self.autosaved_pours = 0;
+        }
+        if self.autosave_interval == 0 || n_pours < self.autosaved_pours + self.autosave_interval {
+            return Ok(false);
+        }
+        self.save(AUTOSAVE_SLOT, n_rows, n_cols, puzzle)?;
+        self.autosaved_pours = n_pours;
+        Ok(true)
+    }
+}
+
+/// 슬롯 이름은 파일 이름이 되므로 글자, 숫자, 빈칸, `-`, `_`만 쓴다.
+pub fn check_slot_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("The slot name is empty".to_string());
+    }
+    if name.trim() != name {
+        return Err("The slot name starts or ends with a space".to_string());
+    }
+    match name
+        .chars()
+        .find(|&c| !(c.is_alphanumeric() || c == ' ' || c == '-' || c == '_'))
+    {
+        Some(c) => Err(format!("The slot name cannot contain {c:?}")),
+        None => Ok(()),
+    }
+}
+
+/// 슬롯을 저장한 때를 지금부터 얼마 전인지로 적는다. 예: `5 min ago`
+pub fn format_age(modified: SystemTime, now: SystemTime) -> String {
+    let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+    let minutes = age.as_secs() / 60;
+    match minutes {
+        0 => "just now".to_string(),
+        1..60 => format!("{minutes} min ago"),
+        60..1440 => format!("{} h ago", minutes / 60),
+        _ => format!("{} days ago", minutes / 1440),
+    }
+}
@@ -1,60 +1,159 @@
-use astar::Astar;
+pub use astar::budget::SolveBudget;
+pub use astar::stats::SolveStats;
+pub use astar::strategy::Strategy;
 use state::INFINITY_USIZE;
+use state::moves::{Move, moves_of_path};
 use state::state::State;
 use std::sync::mpsc; // Multiple Producer, Single Consumer 채널
-use std::{thread, usize};
+use std::thread;
+use std::time::Duration;
+
+use crate::known_answers::KnownAnswers;
+
+/// 큰 판에서도 힌트가 이 안에 오도록 솔버에 주는 기본 한도
+pub const HINT_BUDGET: SolveBudget = SolveBudget {
+    max_nodes: None,
+    max_time: Some(Duration::from_secs(10)),
+};
 
 // A* 결과의 수신자 (리시버) 타입을 정의합니다.
-// Vec<State>는 A* 탐색 결과 경로입니다. None은 경로를 찾지 못했음을 의미합니다.
-type DataReceiver = mpsc::Receiver<Option<Vec<State>>>;
+// Vec<Move>는 A* 탐색 결과 경로를 붓기로 바꾼 것입니다. None은 경로를 찾지 못했음을 의미합니다.
+// bool은 한도에 닿아 찾은 도중의 경로인지 여부입니다.
+type DataReceiver = mpsc::Receiver<(Option<Vec<Move>>, bool)>;
 type StopSender = mpsc::Sender<()>; // 스레드에 종료 신호를 보낼 Sender
+// 탐색하는 동안 REPORT_INTERVAL마다, 그리고 끝났을 때 한 번 통계가 온다.
+type StatsReceiver = mpsc::Receiver<SolveStats>;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Solver {
     data_receiver: Option<DataReceiver>,
     stop_sender: Option<StopSender>,
-    pub path: Option<Option<Vec<State>>>,
+    stats_receiver: Option<StatsReceiver>,
+    strategy: Strategy,
+    budget: SolveBudget,
+    // 스레드가 풀고 있는 상태. 답이 오면 known에 넣는다.
+    solving_state: Option<State>,
+    // 지금의 strategy로 찾은 답들. 같은 상태를 다시 풀면 탐색하지 않고 여기서 꺼낸다.
+    known: KnownAnswers,
+    pub moves: Option<Option<Vec<Move>>>,
+    /// solve()에 넘긴 상태가 `State::is_dead()`이면 true. 그때는 탐색하지 않고 moves를 Some(None)으로 둔다.
+    pub dead_end: bool,
+    /// moves가 한도에 닿아 찾은 도중의 경로이면 true. 가장 짧지 않거나 목표까지 가지 않을 수 있다.
+    pub partial: bool,
+    /// 가장 최근에 받은 통계
+    pub stats: Option<SolveStats>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self {
+            data_receiver: None,
+            stop_sender: None,
+            stats_receiver: None,
+            strategy: Strategy::default(),
+            budget: HINT_BUDGET,
+            solving_state: None,
+            known: KnownAnswers::default(),
+            moves: None,
+            dead_end: false,
+            partial: false,
+            stats: None,
+        }
+    }
 }
 
 impl Solver {
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// 다음 solve()부터 쓴다. 다른 방법으로 찾은 답은 길이가 다를 수 있으므로 기억한 답을 모두 잊는다.
+    pub fn set_strategy(&mut self, strategy: Strategy) {
+        if strategy != self.strategy {
+            self.known.clear();
+        }
+        self.strategy = strategy;
+    }
+
+    pub fn budget(&self) -> SolveBudget {
+        self.budget
+    }
+
+    /// 다음 solve()부터 쓴다.
+    pub fn set_budget(&mut self, budget: SolveBudget) {
+        self.budget = budget;
+    }
+
+    /// 스레드가 아직 답을 찾고 있으면 true
+    pub fn is_solving(&self) -> bool {
+        self.data_receiver.is_some()
+    }
+
     pub fn remaining_step(&self) -> Option<usize> {
-        self.path.as_ref().map(|path| match path {
-            Some(path) => path.len() - 1,
+        self.moves.as_ref().map(|moves| match moves {
+            Some(moves) => moves.len(),
             None => INFINITY_USIZE,
         })
     }
 
-    pub fn hint(&self) -> Option<&State> {
-        if let Some(path) = self.path.as_ref() {
-            if let Some(path) = path {
-                if path.len() > 1 {
-                    return Some(&path[1]);
-                }
-            }
+    /// 솔버가 알려 주는 다음 붓기. 비커 번호는 solve()에 넘긴 상태의 것이다.
+    pub fn hint(&self) -> Option<Move> {
+        match self.moves.as_ref() {
+            Some(Some(moves)) => moves.first().copied(),
+            _ => None,
         }
-        None
     }
 
     // solve 함수는 이제 A* 탐색을 시작하고, Receiver를 Solver 내부에 저장합니다.
     pub fn solve(&mut self, initial_state: &State) {
         self.stop();
-        self.path = None;
+        // 멈춘 스레드가 보내는 답을 받지 않는다.
+        self.data_receiver = None;
+        self.stats_receiver = None;
+        self.solving_state = None;
+        self.moves = None;
+        self.partial = false;
+        self.stats = None;
+
+        // 풀 수 없음이 바로 증명되면 상태 공간을 모두 살펴볼 필요가 없다.
+        self.dead_end = initial_state.is_dead();
+        if self.dead_end {
+            self.moves = Some(None);
+            return;
+        }
+        // 힌트를 따라 붓거나 되돌린 상태는 이미 답을 알고 있다.
+        if let Some(moves) = self.known.get(initial_state) {
+            self.moves = Some(moves);
+            return;
+        }
 
         let (data_tx, data_rx) = mpsc::channel(); // Sender(data_tx)와 Receiver(data_rx)를 생성합니다.
         let (stop_tx, stop_rx) = mpsc::channel();
+        let (stats_tx, stats_rx) = mpsc::channel();
 
         let state_for_thread = initial_state.clone(); // 초기 상태를 클론하여 스레드로 이동
+        let strategy = self.strategy;
+        let budget = self.budget;
 
         thread::spawn(move || {
-            let mut astar = Astar::new();
-            let found_path = astar.find_path(state_for_thread, stop_rx); // A* 탐색 수행
+            let mut solver = strategy.solver();
+            solver.report_to(stats_tx.clone());
+            solver.set_budget(budget);
+            let found_path = solver.find_path(state_for_thread, stop_rx); // A* 탐색 수행
+            // 결과보다 먼저 보내야 check()가 결과와 함께 마지막 통계를 받는다.
+            let _ = stats_tx.send(solver.stats());
+            let found_moves = found_path
+                .map(|path| moves_of_path(&path).expect("A* path is not a sequence of pours"));
 
-            data_tx.send(found_path).expect("Failed to send A* path");
+            // 그 사이에 solve()를 다시 불렀으면 받는 쪽이 없다.
+            let _ = data_tx.send((found_moves, solver.budget_exhausted()));
         });
 
         // 생성된 Sender와 Receiver를 Solver 내부에 저장합니다.
         self.data_receiver = Some(data_rx);
         self.stop_sender = Some(stop_tx);
+        self.stats_receiver = Some(stats_rx);
+        self.solving_state = Some(initial_state.clone());
     }
 
     // A* 스레드로부터 결과가 도착했는지 확인하고, 도착했다면 Solver 내부에 설정합니다.
@@ -62,20 +161,37 @@ impl Solver {
     pub fn check(&mut self) {
         if let Some(receiver) = &self.data_receiver {
             match receiver.try_recv() {
-                Ok(data) => {
-                    self.path = Some(data);
+                Ok((data, partial)) => {
+                    // 한도에 닿아 찾은 도중의 경로는 답이 아니다.
+                    if let Some(state) = self.solving_state.take()
+                        && !partial
+                    {
+                        self.known.insert(&state, data.as_deref());
+                    }
+                    self.moves = Some(data);
+                    self.partial = partial;
                     self.data_receiver = None; // 더 이상 이 Receiver는 유효하지 않으므로 제거
                     self.stop_sender = None;
                 }
                 Err(mpsc::TryRecvError::Empty) => {} // 아직 메시지가 도착하지 않음
                 Err(mpsc::TryRecvError::Disconnected) => {
                     // Sender가 끊어진 경우 (스레드가 종료되었으나 메시지를 보내지 못한 경우 등)
-                    self.path = None;
+                    self.moves = None;
                     self.data_receiver = None; // 더 이상 이 Receiver는 유효하지 않으므로 제거
                     self.stop_sender = None;
+                    self.solving_state = None;
                 }
             }
         }
+        // 결과를 받았으면 마지막 통계도 이미 와 있다.
+        if let Some(receiver) = &self.stats_receiver {
+            while let Ok(stats) = receiver.try_recv() {
+                self.stats = Some(stats);
+            }
+            if self.data_receiver.is_none() {
+                self.stats_receiver = None;
+            }
+        }
     }
 
     pub fn stop(&mut self) {
@@ -0,0 +1,457 @@
+//! 게임 기록과 통계. 판마다 붓기의 수와 가장 짧은 수, 걸린 시간, 힌트와 되돌리기의 수를 남기고,
+//! 모은 기록으로 연승과 판의 크기별 평균을 낸다.
+//!
+//! 파일의 머리말은 save_file과 같은 모양이다: STATS_MAGIC, STATS_VERSION, 본문의 길이, 본문의 CRC-32
+//! 본문: 기록의 개수, 기록마다 `PuzzleConfig::save()`, 이겼는지, 붓기, 가장 짧은 수, 밀리초, 힌트, 되돌리기
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use state::{Cord, config::PuzzleConfig};
+
+use crate::save_file::{crc32, read_u32};
+
+pub const STATS_MAGIC: &[u8; 4] = b"WPST";
+pub const STATS_VERSION: u32 = 1;
+/// 저장 폴더 안에서 통계를 적는 파일
+pub const STATS_FILE_NAME: &str = "stats.wps";
+
+// 가장 짧은 수를 모를 때 파일에 적는 값
+const UNKNOWN_OPTIMAL: u32 = u32::MAX;
+
+/// 통계 파일을 읽지 못한 이유
+#[derive(Debug)]
+pub enum StatsError {
+    Io(io::Error),
+    /// STATS_MAGIC으로 시작하지 않는다.
+    NotStatistics,
+    /// 파일이 중간에 끝났다.
+    Truncated,
+    /// 읽을 것이 끝났는데 바이트가 남았다.
+    TrailingBytes(usize),
+    /// 이 프로그램보다 새 버전의 파일이다.
+    UnsupportedVersion(u32),
+    ChecksumMismatch {
+        expected: u32,
+        actual: u32,
+    },
+    InvalidConfig(String),
+    /// 기록에 있을 수 없는 값이 있다.
+    InvalidRecord(String),
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "cannot read the statistics file: {e}"),
+            Self::NotStatistics => write!(f, "not a statistics file"),
+            Self::Truncated => write!(f, "the statistics file is cut short"),
+            Self::TrailingBytes(n) => {
+                write!(f, "the statistics file has {n} unexpected bytes at the end")
+            }
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "statistics file version {version} is not supported (expected {STATS_VERSION})"
+            ),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "the statistics file is corrupt (checksum {actual:08x}, expected {expected:08x})"
+            ),
+            Self::InvalidConfig(e) => write!(f, "invalid puzzle config in a game record: {e}"),
+            Self::InvalidRecord(e) => write!(f, "invalid game record: {e}"),
+        }
+    }
+}
+
+impl Error for StatsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StatsError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            Self::Truncated
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+/// 끝난 판 하나의 기록
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub config: PuzzleConfig,
+    /// 다 풀었으면 true, 풀지 않고 다른 판으로 넘어갔으면 false
+    pub won: bool,
+    /// 끝났을 때의 단계. 이긴 판은 푼 경로의 길이이다.
+    pub moves: usize,
+    /// 처음 판을 푸는 가장 짧은 수. 솔버가 찾기 전에 붓기 시작했거나 가려진 층이 있으면 None
+    pub optimal: Option<usize>,
+    pub elapsed: Duration,
+    pub n_hints: usize,
+    pub n_undos: usize,
+}
+
+impl GameResult {
+    /// 가장 짧은 수를 붓기로 나눈 백분율. 가장 짧게 풀면 100
+    pub fn score(&self) -> Option<usize> {
+        match self.optimal {
+            Some(optimal) if self.won && self.moves > 0 => Some(100 * optimal / self.moves),
+            _ => None,
+        }
+    }
+
+    /// 끝난 판의 요약에 보일 줄들
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let optimal = self
+            .optimal
+            .map_or("?".to_owned(), |optimal| optimal.to_string());
+        if self.won {
+            lines.push(format!(
+                "Solved in {} moves (optimal {optimal})",
+                self.moves
+            ));
+        } else {
+            lines.push(format!("Gave up after {} moves", self.moves));
+        }
+        if let Some(score) = self.score() {
+            lines.push(format!("Score {score}"));
+        }
+        lines.push(format!("Time {}", format_duration(self.elapsed)));
+        lines.push(format!("Hints {}, undos {}", self.n_hints, self.n_undos));
+        lines
+    }
+
+    fn save<W: Write>(&self, bw: &mut W) -> Result<(), Box<dyn Error>> {
+        self.config.save(bw)?;
+        let optimal = self
+            .optimal
+            .map_or(UNKNOWN_OPTIMAL, |optimal| optimal as u32);
+        let millis = self.elapsed.as_millis().min(u32::MAX as u128) as u32;
+        for value in [
+            self.won as u32,
+            self.moves as u32,
+            optimal,
+            millis,
+            self.n_hints as u32,
+            self.n_undos as u32,
+        ] {
+            bw.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read(bytes: &mut &[u8]) -> Result<Self, StatsError> {
+        let config = PuzzleConfig::load(bytes).map_err(|e| match e.downcast::<io::Error>() {
+            Ok(e) => StatsError::from(*e),
+            Err(e) => StatsError::InvalidConfig(e.to_string()),
+        })?;
+        let won = match read_u32(bytes)? {
+            0 => false,
+            1 => true,
+            won => return Err(StatsError::InvalidRecord(format!("invalid result {won}"))),
+        };
+        let moves = read_u32(bytes)? as usize;
+        let optimal = match read_u32(bytes)? {
+            UNKNOWN_OPTIMAL => None,
+            optimal => Some(optimal as usize),
+        };
+        let elapsed = Duration::from_millis(read_u32(bytes)? as u64);
+        let n_hints = read_u32(bytes)? as usize;
+        let n_undos = read_u32(bytes)? as usize;
+        Ok(Self {
+            config,
+            won,
+            moves,
+            optimal,
+            elapsed,
+            n_hints,
+            n_undos,
+        })
+    }
+}
+
+/// 진행 중인 판에서 모으는 기록. Puzzle이 가지고 있다.
+#[derive(Debug)]
+pub(crate) struct GameTracker {
+    started: Instant,
+    pub(crate) optimal: Option<usize>,
+    pub(crate) n_hints: usize,
+    // 마지막으로 힌트를 센 판
+    hinted: Option<Cord>,
+    pub(crate) n_undos: usize,
+    // 기록을 한 번 돌려주었으면 true
+    pub(crate) recorded: bool,
+}
+
+impl Default for GameTracker {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            optimal: None,
+            n_hints: 0,
+            hinted: None,
+            n_undos: 0,
+            recorded: false,
+        }
+    }
+}
+
+impl GameTracker {
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// cord인 판에서 힌트를 보았다. 힌트를 보고 그대로 붓거나 여러 번 눌러도 같은 판에서는 한 번만 센다.
+    pub(crate) fn count_hint(&mut self, cord: Cord) {
+        if self.hinted.as_ref() != Some(&cord) {
+            self.n_hints += 1;
+            self.hinted = Some(cord);
+        }
+    }
+
+    pub(crate) fn result(&self, config: PuzzleConfig, won: bool, moves: usize) -> GameResult {
+        GameResult {
+            config,
+            won,
+            moves,
+            optimal: self.optimal,
+            elapsed: self.elapsed(),
+            n_hints: self.n_hints,
+            n_undos: self.n_undos,
+        }
+    }
+}
+
+/// 판의 크기. 비커의 개수와 용량이 같으면 같은 크기로 본다.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoardSize {
+    pub n_beakers: usize,
+    pub capacity: usize,
+}
+
+impl From<PuzzleConfig> for BoardSize {
+    fn from(config: PuzzleConfig) -> Self {
+        Self {
+            n_beakers: config.n_beakers(),
+            capacity: config.capacity,
+        }
+    }
+}
+
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} beakers x {}", self.n_beakers, self.capacity)
+    }
+}
+
+/// 한 크기의 판들을 모은 것
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SizeSummary {
+    pub size: BoardSize,
+    pub n_played: usize,
+    pub n_won: usize,
+    /// 이긴 판의 평균. 이긴 판이 없으면 None
+    pub average_moves: Option<f64>,
+    pub average_time: Option<Duration>,
+    /// 점수를 낸 판의 평균
+    pub average_score: Option<f64>,
+}
+
+impl fmt::Display for SizeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: won {}/{}", self.size, self.n_won, self.n_played)?;
+        if let (Some(moves), Some(time)) = (self.average_moves, self.average_time) {
+            write!(f, ", avg {moves:.1} moves in {}", format_duration(time))?;
+        }
+        if let Some(score) = self.average_score {
+            write!(f, ", avg score {score:.0}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 지금까지 끝난 판의 기록. 오래된 판부터
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Statistics {
+    games: Vec<GameResult>,
+}
+
+impl Statistics {
+    pub fn games(&self) -> &[GameResult] {
+        &self.games
+    }
+
+    pub fn record(&mut self, result: GameResult) {
+        self.games.push(result);
+    }
+
+    pub fn n_played(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn n_won(&self) -> usize {
+        self.games.iter().filter(|game| game.won).count()
+    }
+
+    /// 가장 최근 판까지 이어서 이긴 판의 수
+    pub fn current_streak(&self) -> usize {
+        self.games.iter().rev().take_while(|game| game.won).count()
+    }
+
+    pub fn best_streak(&self) -> usize {
+        self.games
+            .split(|game| !game.won)
+            .map(|wins| wins.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 크기마다 모은 것. 작은 판부터
+    pub fn by_size(&self) -> Vec<SizeSummary> {
+        let mut sizes: BTreeMap<BoardSize, Vec<&GameResult>> = BTreeMap::new();
+        for game in &self.games {
+            sizes.entry(game.config.into()).or_default().push(game);
+        }
+        sizes
+            .into_iter()
+            .map(|(size, games)| {
+                let won: Vec<_> = games.iter().filter(|game| game.won).collect();
+                let scores: Vec<_> = won.iter().filter_map(|game| game.score()).collect();
+                let average = |sum: f64, n: usize| (n > 0).then(|| sum / n as f64);
+                SizeSummary {
+                    size,
+                    n_played: games.len(),
+                    n_won: won.len(),
+                    average_moves: average(
+                        won.iter().map(|game| game.moves as f64).sum(),
+                        won.len(),
+                    ),
+                    average_time: (!won.is_empty()).then(|| {
+                        won.iter().map(|game| game.elapsed).sum::<Duration>() / won.len() as u32
+                    }),
+                    average_score: average(
+                        scores.iter().map(|&score| score as f64).sum(),
+                        scores.len(),
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    /// 끝난 판의 요약 아래에 보일 줄들. 연승과 그 판의 크기의 평균
+    pub fn summary(&self, config: PuzzleConfig) -> Vec<String> {
+        let size = BoardSize::from(config);
+        let mut lines = vec![format!(
+            "Streak {} (best {}), won {}/{}",
+            self.current_streak(),
+            self.best_streak(),
+            self.n_won(),
+            self.n_played()
+        )];
+        lines.extend(
+            self.by_size()
+                .iter()
+                .filter(|summary| summary.size == size)
+                .map(|summary| summary.to_string()),
+        );
+        lines
+    }
+
+    pub fn save<W: Write>(&self, bw: &mut W) -> Result<(), Box<dyn Error>> {
+        let mut body = Vec::new();
+        body.write_all(&(self.games.len() as u32).to_le_bytes())?;
+        for game in &self.games {
+            game.save(&mut body)?;
+        }
+
+        bw.write_all(STATS_MAGIC)?;
+        bw.write_all(&STATS_VERSION.to_le_bytes())?;
+        bw.write_all(&(body.len() as u32).to_le_bytes())?;
+        bw.write_all(&crc32(&body).to_le_bytes())?;
+        bw.write_all(&body)?;
+        Ok(())
+    }
+
+    pub fn load<R: Read>(br: &mut R) -> Result<Self, StatsError> {
+        let mut bytes = Vec::new();
+        br.read_to_end(&mut bytes)?;
+        let Some(mut bytes) = bytes.strip_prefix(STATS_MAGIC) else {
+            return Err(StatsError::NotStatistics);
+        };
+        let version = read_u32(&mut bytes)?;
+        if version != STATS_VERSION {
+            return Err(StatsError::UnsupportedVersion(version));
+        }
+        let len = read_u32(&mut bytes)? as usize;
+        let expected = read_u32(&mut bytes)?;
+        if bytes.len() < len {
+            return Err(StatsError::Truncated);
+        }
+        if bytes.len() > len {
+            return Err(StatsError::TrailingBytes(bytes.len() - len));
+        }
+        let actual = crc32(bytes);
+        if actual != expected {
+            return Err(StatsError::ChecksumMismatch { expected, actual });
+        }
+        let n_games = read_u32(&mut bytes)? as usize;
+        // 기록마다 11개의 u32를 적는다.
+        if bytes.len() < n_games.saturating_mul(44) {
+            return Err(StatsError::Truncated);
+        }
+        let games = (0..n_games)
+            .map(|_| GameResult::read(&mut bytes))
+            .collect::<Result<_, _>>()?;
+        if !bytes.is_empty() {
+            return Err(StatsError::TrailingBytes(bytes.len()));
+        }
+        Ok(Self { games })
+    }
+
+    /// 파일이 없으면 빈 통계
+    pub fn load_from(path: &Path) -> Result<Self, StatsError> {
+        match File::open(path) {
+            Ok(file) => Self::load(&mut BufReader::new(file)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 슬롯처럼 임시 파일에 쓴 다음 이름을 바꾼다.
+    pub fn save_to(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        self.save(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+/// 걸린 시간. 예: `3:05`, `1:02:03`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}")
+    } else {
+        format!("{minutes}:{secs:02}")
+    }
+}
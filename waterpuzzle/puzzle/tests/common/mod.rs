@@ -0,0 +1,47 @@
+//! 여러 테스트가 함께 쓰는, 정해진 판에서 가지가 생기도록 부은 게임
+
+use puzzle::puzzle::Puzzle;
+use state::{config::PuzzleConfig, level_code::LevelCode};
+
+/// 한 번 붓고 되돌린 다음 다른 붓기를 해서 가지를 만들고, 그 가지에서 step 번까지 부은 판.
+/// 무작위 판은 부을 곳이 먼저 없어질 수 있으므로 정해진 판을 쓴다.
+pub fn played(config: PuzzleConfig, step: usize) -> Puzzle {
+    let mut puzzle = Puzzle::from_level_code(LevelCode::new(config, 1));
+    pour_until(&mut puzzle, 1);
+    puzzle.undo();
+    let n = puzzle.n_beakers();
+    let branched = (0..n)
+        .flat_map(|from| (0..n).map(move |to| (from, to)))
+        .any(|(from, to)| {
+            puzzle.on_right_click(Some(from));
+            puzzle.on_right_click(Some(to));
+            puzzle.on_right_click(None);
+            if puzzle.step() == 0 {
+                return false;
+            }
+            puzzle.undo();
+            if puzzle.n_redo_branches() < 2 {
+                return false;
+            }
+            // redo는 마지막으로 지나간 새 가지로 간다.
+            puzzle.redo();
+            true
+        });
+    assert!(branched, "no other first move");
+    pour_until(&mut puzzle, step);
+    puzzle
+}
+
+/// step이 될 때까지 부을 수 있는 첫 비커를 왼쪽 클릭한다.
+fn pour_until(puzzle: &mut Puzzle, step: usize) {
+    while puzzle.step() < step {
+        let before = puzzle.step();
+        for index in 0..puzzle.n_beakers() {
+            puzzle.on_left_click(index);
+            if puzzle.step() > before {
+                break;
+            }
+        }
+        assert!(puzzle.step() > before, "no move left");
+    }
+}
@@ -0,0 +1,26 @@
+//! 비커를 선택하면 부었을 때 풀 수 없게 되는 비커를 알려 주는지 확인한다.
+
+use puzzle::puzzle::Puzzle;
+
+#[test]
+fn marks_pours_that_lose_the_game() {
+    // 0의 Y를 빈 비커 3에 부으면 Y 두 개가 갈 곳이 없어진다.
+    let mut puzzle = Puzzle::from_code("YBY|BRR|YBR|...").unwrap();
+    assert!(!puzzle.is_dead_target_index(3));
+
+    puzzle.on_right_click(Some(0));
+    let dead: Vec<usize> = (0..puzzle.n_beakers())
+        .filter(|&index| puzzle.is_dead_target_index(index))
+        .collect();
+    assert_eq!(dead, [3]);
+
+    // 1의 RR은 3에 부어도 괜찮다.
+    puzzle.on_right_click(Some(0));
+    assert!(!puzzle.is_dead_target_index(3));
+    puzzle.on_right_click(Some(1));
+    assert!(!puzzle.is_dead_target_index(3));
+    puzzle.on_right_click(Some(3));
+    assert_eq!(puzzle.n_pours(), 1);
+    assert!(!puzzle.is_dead_end());
+    assert!((0..puzzle.n_beakers()).all(|index| !puzzle.is_dead_target_index(index)));
+}
@@ -0,0 +1,136 @@
+//! 같은 seed에서 같은 판을 만드는지, 판의 품질을 맞게 재는지, 한도에 닿은 판을 버리는지 확인한다.
+
+use std::{collections::HashSet, thread, time::Duration};
+
+use astar::budget::SolveBudget;
+use puzzle::generator::{Difficulty, EVALUATE_BUDGET, LevelGenerator, PendingLevel};
+use state::{config::PuzzleConfig, level_code::LevelCode, state::State};
+
+/// 솔버를 쓰지 않고 너비 우선으로 찾은 가장 짧은 풀이의 길이. 풀 수 없으면 None
+fn bfs_length(initial: State) -> Option<usize> {
+    let mut seen = HashSet::from([initial.clone()]);
+    let mut frontier = vec![initial];
+    for length in 0.. {
+        if frontier.is_empty() {
+            return None;
+        }
+        if frontier.iter().any(State::is_search_goal) {
+            return Some(length);
+        }
+        let mut next_frontier = Vec::new();
+        for state in &frontier {
+            for i in 0..state.n_beakers() {
+                for j in 0..state.n_beakers() {
+                    let mut next = state.clone();
+                    if next.pour(i, j).is_ok() && seen.insert(next.clone()) {
+                        next_frontier.push(next);
+                    }
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    unreachable!()
+}
+
+#[test]
+fn same_seed_gives_same_level() {
+    let generator = LevelGenerator::new(PuzzleConfig::new(5, 2, 4), 10..=14).with_seed(42);
+    let level = generator.generate().unwrap();
+    assert_eq!(generator.generate(), Some(level));
+    assert!((10..=14).contains(&level.quality.optimal_length));
+    assert_eq!(
+        LevelGenerator::evaluate(level.level_code, EVALUATE_BUDGET),
+        Some(level.quality)
+    );
+
+    let other = generator.clone().with_seed(43).generate().unwrap();
+    assert_ne!(other.level_code, level.level_code);
+}
+
+#[test]
+fn measures_the_shortest_solution() {
+    for config in [
+        PuzzleConfig::new(3, 1, 3),
+        PuzzleConfig::new(3, 2, 4),
+        PuzzleConfig::new(4, 2, 3),
+    ] {
+        for seed in 0..4 {
+            let level_code = LevelCode::new(config, seed);
+            let quality = LevelGenerator::evaluate(level_code, EVALUATE_BUDGET);
+
+            let mut state = State::new(config);
+            state.generate_with_seed(seed);
+            let length = bfs_length(state);
+            assert_eq!(
+                quality.map(|quality| quality.optimal_length),
+                length,
+                "{level_code}"
+            );
+            if let Some(quality) = quality {
+                assert!(quality.branching_factor >= 1.0, "{level_code}");
+            }
+        }
+    }
+}
+
+#[test]
+fn rejects_levels_over_budget() {
+    let level_code = LevelCode::new(PuzzleConfig::new(5, 2, 4), 0);
+    let tiny = SolveBudget::UNLIMITED.with_max_nodes(1);
+    assert_eq!(LevelGenerator::evaluate(level_code, tiny), None);
+    assert!(LevelGenerator::evaluate(level_code, EVALUATE_BUDGET).is_some());
+
+    let generator = LevelGenerator::new(level_code.config, 0..=usize::MAX)
+        .with_seed(0)
+        .with_max_attempts(5)
+        .with_budget(tiny);
+    assert_eq!(generator.generate(), None);
+}
+
+#[test]
+fn evaluates_default_boards_within_budget() {
+    let config = PuzzleConfig::default();
+    for seed in 0..4 {
+        let level_code = LevelCode::new(config, seed);
+        let quality = LevelGenerator::evaluate(level_code, EVALUATE_BUDGET);
+        assert!(quality.is_some(), "seed {seed}");
+        // 가려진 층이 있어도 같은 판으로 잰다.
+        let mystery = LevelCode::new(config.with_mystery(true), seed);
+        assert_eq!(LevelGenerator::evaluate(mystery, EVALUATE_BUDGET), quality);
+    }
+}
+
+#[test]
+fn generates_each_difficulty() {
+    let config = PuzzleConfig::default();
+    assert!(Difficulty::Any.generator(config).is_none());
+    for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+        let optimal_lengths = difficulty.optimal_lengths(config).unwrap();
+        let generator = difficulty.generator(config).unwrap().with_seed(0);
+        let level = generator.generate().unwrap();
+        assert!(
+            optimal_lengths.contains(&level.quality.optimal_length),
+            "{difficulty}"
+        );
+    }
+}
+
+#[test]
+fn pending_level_arrives_from_a_thread() {
+    let config = PuzzleConfig::new(5, 2, 4);
+    assert!(PendingLevel::spawn(config, Difficulty::Any).is_none());
+
+    let pending = PendingLevel::spawn(config, Difficulty::Easy).unwrap();
+    assert_eq!(pending.config(), config);
+    let level = loop {
+        match pending.check() {
+            Some(level) => break level,
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+    };
+    let level = level.unwrap();
+    assert_eq!(level.level_code.config, config);
+    let optimal_lengths = Difficulty::Easy.optimal_lengths(config).unwrap();
+    assert!(optimal_lengths.contains(&level.quality.optimal_length));
+}
@@ -0,0 +1,87 @@
+//! 되돌린 다음 다른 붓기를 해도 원래 가지가 남고, 나무 전체가 저장되는지 확인한다.
+
+use puzzle::history::History;
+use state::Cord;
+
+fn cord(x: u64) -> Cord {
+    vec![x, 0]
+}
+
+/// 0 -> 1 -> 2 를 두고 1로 되돌린 다음 3으로 간 나무. 지금 노드는 3이다.
+fn branched() -> History {
+    let mut history = History::new(cord(0));
+    history.push(cord(1));
+    history.push(cord(2));
+    history.undo();
+    history.push(cord(3));
+    history
+}
+
+#[test]
+fn push_after_undo_keeps_the_old_branch() {
+    let mut history = branched();
+    assert_eq!(history.n_nodes(), 4);
+    assert_eq!(history.step(), 2);
+    assert_eq!(history.redo(), None);
+
+    assert_eq!(history.undo(), Some(&cord(1)));
+    assert_eq!(history.n_branches(), 2);
+    // redo는 마지막으로 지나간 가지로 간다.
+    assert_eq!(history.branch(), 1);
+    assert_eq!(history.redo(), Some(&cord(3)));
+
+    history.undo();
+    history.select_branch(0);
+    assert_eq!(history.redo(), Some(&cord(2)));
+    assert_eq!(history.step(), 2);
+
+    // 이미 있는 가지와 같은 붓기는 새 가지를 만들지 않는다.
+    history.undo();
+    history.push(cord(3));
+    assert_eq!(history.n_nodes(), 4);
+    assert_eq!(history.undo_all(), &cord(0));
+    assert_eq!(history.step(), 0);
+    assert_eq!(history.undo(), None);
+}
+
+#[test]
+fn save_and_load_the_whole_tree() {
+    let history = branched();
+    let mut bytes = Vec::new();
+    history.save(&mut bytes).unwrap();
+
+    let mut loaded = History::default();
+    loaded.load(&mut bytes.as_slice(), 2).unwrap();
+    assert_eq!(loaded.n_nodes(), 4);
+    assert_eq!(loaded.step(), 2);
+    assert_eq!(loaded.undo(), Some(&cord(1)));
+    assert_eq!(loaded.n_branches(), 2);
+    assert_eq!(loaded.branch(), 1);
+    loaded.select_branch(0);
+    assert_eq!(loaded.redo(), Some(&cord(2)));
+
+    // 가지의 정보가 중간에 끊긴 파일
+    let mut loaded = History::default();
+    assert!(loaded.load(&mut &bytes[..bytes.len() - 1], 2).is_err());
+}
+
+#[test]
+fn loads_a_linear_history() {
+    // (cord의 개수, 지금 위치, cord들)만 있는 예전 파일
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&3u32.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    for x in 0..3 {
+        for waters in cord(x) {
+            bytes.extend_from_slice(&waters.to_le_bytes());
+        }
+    }
+
+    let mut history = History::default();
+    history.load(&mut bytes.as_slice(), 2).unwrap();
+    assert_eq!(history.n_nodes(), 3);
+    assert_eq!(history.step(), 1);
+    assert_eq!(history.n_branches(), 1);
+    assert_eq!(history.redo(), Some(&cord(2)));
+    assert_eq!(history.undo_all(), &cord(0));
+}
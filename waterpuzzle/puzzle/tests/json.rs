@@ -0,0 +1,110 @@
+//! `serde` 기능: 게임을 JSON으로 내보내고 다시 읽으면 같은 판과 단계가 되는지 확인한다.
+
+mod common;
+
+use common::played;
+use puzzle::{history::History, puzzle::Puzzle};
+use state::config::PuzzleConfig;
+
+#[test]
+fn export_and_import_a_game() {
+    for config in [
+        PuzzleConfig::new(5, 2, 4),
+        PuzzleConfig::new(6, 2, 5).with_mystery(true),
+    ] {
+        let mut puzzle = played(config, 3);
+        puzzle.undo();
+        let json = puzzle.export_json().unwrap();
+        let mut loaded = Puzzle::import_json(&json).unwrap();
+        assert_eq!(loaded.config(), config);
+        assert_eq!(loaded.step(), puzzle.step());
+        assert_eq!(loaded.main_line_moves(), puzzle.main_line_moves());
+        assert_eq!(loaded.export_json().unwrap(), json);
+        for index in 0..puzzle.n_beakers() {
+            assert_eq!(loaded.beaker(index), puzzle.beaker(index));
+            assert_eq!(loaded.beaker(index).hidden(), puzzle.beaker(index).hidden());
+        }
+
+        // 고른 가지로 redo할 수 있다.
+        let n_moves = puzzle.main_line_moves().unwrap().len();
+        for _ in loaded.step()..n_moves + 1 {
+            loaded.redo();
+        }
+        assert_eq!(loaded.step(), n_moves);
+    }
+}
+
+#[test]
+fn imports_a_hand_written_game() {
+    let json = r#"{
+        "initial": {
+            "config": {"n_colors": 2, "n_empty": 1, "capacity": 4},
+            "beakers": [{"waters": "RRBB"}, {"waters": "BBRR"}, {"waters": "...."}]
+        },
+        "moves": [
+            {"from": 0, "to": 2, "color": 2, "count": 2},
+            {"from": 1, "to": 0, "color": 1, "count": 2},
+            {"from": 1, "to": 2, "color": 2, "count": 2}
+        ],
+        "step": 3
+    }"#;
+    let puzzle = Puzzle::import_json(json).unwrap();
+    assert!(puzzle.is_completed());
+    assert_eq!(puzzle.step(), 3);
+
+    let mut puzzle = Puzzle::import_json(&json.replace(r#""step": 3"#, r#""step": 1"#)).unwrap();
+    assert_eq!(puzzle.step(), 1);
+    assert!(!puzzle.is_completed());
+    puzzle.redo();
+    puzzle.redo();
+    assert!(puzzle.is_completed());
+
+    for (from, to, reason) in [
+        (r#""step": 3"#, r#""step": 4"#, "step 4 is beyond 3 moves"),
+        (
+            r#""from": 0, "to": 2, "color": 2"#,
+            r#""from": 0, "to": 2, "color": 1"#,
+            "move 0",
+        ),
+        (r#""from": 1, "to": 0"#, r#""from": 2, "to": 0"#, "move 1"),
+        (r#""RRBB""#, r#""RRBR""#, "invalid initial board"),
+    ] {
+        let e = Puzzle::import_json(&json.replace(from, to)).unwrap_err();
+        assert!(e.to_string().contains(reason), "{e}");
+    }
+}
+
+#[test]
+fn round_trip_of_a_board_from_text() {
+    for code in [
+        "RRBB|BBRR|....",
+        "BBBB|RRRR|....|....",
+        "YBY|BYB|RRR|...",
+    ] {
+        let puzzle = Puzzle::from_code(code).unwrap();
+        let loaded = Puzzle::import_json(&puzzle.export_json().unwrap()).unwrap();
+        assert_eq!(loaded.config(), puzzle.config(), "{code}");
+        assert_eq!(loaded.visible_cord(), puzzle.visible_cord(), "{code}");
+    }
+}
+
+#[test]
+fn history_round_trip() {
+    let mut history = History::new(vec![0x0101, 0]);
+    history.push(vec![0x01, 0x01]);
+    history.undo();
+    history.push(vec![0, 0x0101]);
+    history.undo();
+
+    let json = serde_json::to_string(&history).unwrap();
+    assert!(json.contains(r#""cord":"RR|""#), "{json}");
+    let mut loaded: History = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.n_nodes(), 3);
+    assert_eq!(loaded.step(), 0);
+    assert_eq!(loaded.n_branches(), 2);
+    assert_eq!(loaded.branch(), 1);
+    assert_eq!(loaded.redo(), Some(&vec![0, 0x0101]));
+
+    let bad = json.replace(r#""parent":0"#, r#""parent":2"#);
+    assert!(serde_json::from_str::<History>(&bad).is_err());
+}
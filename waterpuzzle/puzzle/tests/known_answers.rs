@@ -0,0 +1,84 @@
+//! 찾은 답을 기억했다가 경로 위의 상태를 다시 풀면 탐색 없이 돌려주는지 확인한다.
+
+use std::{thread, time::Duration};
+
+use puzzle::{known_answers::KnownAnswers, solver::Solver};
+use state::{config::PuzzleConfig, state::State};
+
+fn generate(seed: u64) -> State {
+    let mut state = State::new(PuzzleConfig::new(5, 2, 4));
+    state.generate_with_seed(seed);
+    state
+}
+
+fn wait(solver: &mut Solver) {
+    while solver.is_solving() {
+        thread::sleep(Duration::from_millis(1));
+        solver.check();
+    }
+}
+
+#[test]
+fn answers_states_on_the_known_path() {
+    let mut solver = Solver::default();
+    let mut state = generate(0);
+    solver.solve(&state);
+    wait(&mut solver);
+    let moves = solver.moves.clone().unwrap().unwrap();
+
+    for (i, mv) in moves.iter().enumerate() {
+        state.apply(*mv).unwrap();
+        solver.solve(&state);
+        assert!(!solver.is_solving());
+        assert_eq!(solver.remaining_step(), Some(moves.len() - i - 1));
+    }
+    assert!(state.is_completed());
+}
+
+#[test]
+fn answers_permuted_states() {
+    let state = generate(1);
+    let mut known = KnownAnswers::default();
+    let mut solver = Solver::default();
+    solver.solve(&state);
+    wait(&mut solver);
+    let moves = solver.moves.clone().unwrap().unwrap();
+    known.insert(&state, Some(&moves));
+    assert_eq!(known.len(), moves.len() + 1);
+
+    // 비커의 순서가 달라도 같은 상태이고, 답은 바뀐 순서로 적는다.
+    let mut beakers = state.beakers.clone();
+    beakers.reverse();
+    let reversed = State::from_beakers(beakers, state.config());
+    let reversed_moves = known.get(&reversed).unwrap().unwrap();
+    assert_eq!(reversed_moves.len(), moves.len());
+    let mut replayed = reversed.clone();
+    for mv in reversed_moves {
+        replayed.apply(mv).unwrap();
+    }
+    assert!(replayed.is_completed());
+
+    assert_eq!(known.get(&generate(2)), None);
+    known.insert(&generate(2), None);
+    assert_eq!(known.get(&generate(2)), Some(None));
+}
+
+#[test]
+fn does_not_answer_states_off_the_path() {
+    let state = generate(3);
+    let mut solver = Solver::default();
+    solver.solve(&state);
+    wait(&mut solver);
+    let moves = solver.moves.clone().unwrap().unwrap();
+
+    // 탐색 중에 만났겠지만 답의 경로 위에 있지 않은 상태는 다시 찾는다.
+    let mut off_path = state.clone();
+    let mv = off_path.useful_moves().find(|&mv| mv != moves[0]).unwrap();
+    off_path.apply(mv).unwrap();
+    let mut on_path = state.clone();
+    on_path.apply(moves[0]).unwrap();
+    assert_ne!(off_path, on_path);
+    solver.solve(&off_path);
+    assert!(solver.is_solving());
+    solver.stop();
+}
@@ -0,0 +1,172 @@
+//! 저장 파일을 다시 읽을 수 있는지, 망가진 파일과 처음 파일을 어떻게 읽는지 확인한다.
+
+mod common;
+
+use common::played;
+use puzzle::{
+    puzzle::Puzzle,
+    save_file::{self, CordError, LoadError},
+};
+use state::{config::PuzzleConfig, state::State};
+
+fn saved(puzzle: &Puzzle) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    save_file::save(&mut bytes, 2, 4, puzzle).unwrap();
+    bytes
+}
+
+#[test]
+fn round_trip() {
+    let mut puzzle = played(PuzzleConfig::new(5, 2, 4), 2);
+    let bytes = saved(&puzzle);
+    let (n_rows, n_cols, mut loaded) = save_file::load(&mut bytes.as_slice()).unwrap();
+    assert_eq!((n_rows, n_cols), (2, 4));
+    assert_eq!(loaded.config(), puzzle.config());
+    assert_eq!(loaded.step(), puzzle.step());
+    assert_eq!(loaded.n_redo_branches(), puzzle.n_redo_branches());
+
+    puzzle.undo();
+    loaded.undo();
+    assert_eq!(loaded.n_redo_branches(), puzzle.n_redo_branches());
+    assert_eq!(saved(&loaded), saved(&puzzle));
+}
+
+#[test]
+fn round_trip_of_a_board_from_text() {
+    for code in [
+        "RRBB|BBRR|....",
+        "BBBB|RRRR|....|....",
+        "YBY|BYB|RRR|...",
+    ] {
+        let puzzle = Puzzle::from_code(code).unwrap();
+        let (_, _, loaded) = save_file::load(&mut saved(&puzzle).as_slice()).unwrap();
+        assert_eq!(loaded.config(), puzzle.config(), "{code}");
+        assert_eq!(loaded.visible_cord(), puzzle.visible_cord(), "{code}");
+    }
+}
+
+#[test]
+fn round_trip_of_other_configs() {
+    for config in [
+        PuzzleConfig::new(14, 1, 4),
+        PuzzleConfig::new(12, 3, 4),
+        PuzzleConfig::new(7, 2, 6),
+    ] {
+        let mut state = State::new(config);
+        state.generate_with_seed(0);
+        assert_eq!(state.config(), config);
+        assert_eq!(state.n_beakers(), config.n_colors + config.n_empty);
+        let n_empty = (0..state.n_beakers())
+            .filter(|&i| state.beaker(i).is_empty())
+            .count();
+        assert_eq!(n_empty, config.n_empty, "{config:?}");
+
+        let puzzle = Puzzle::from_state(state);
+        let mut bytes = Vec::new();
+        save_file::save(&mut bytes, 3, 5, &puzzle).unwrap();
+        let (n_rows, n_cols, loaded) = save_file::load(&mut bytes.as_slice()).unwrap();
+        assert_eq!((n_rows, n_cols), (3, 5));
+        assert_eq!(loaded.config(), config);
+        assert_eq!(loaded.visible_cord(), puzzle.visible_cord(), "{config:?}");
+    }
+}
+
+#[test]
+fn rejects_damaged_files() {
+    let bytes = saved(&played(PuzzleConfig::new(5, 2, 4), 2));
+
+    let mut flipped = bytes.clone();
+    *flipped.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        save_file::load(&mut flipped.as_slice()),
+        Err(LoadError::ChecksumMismatch { .. })
+    ));
+
+    let cut = &bytes[..bytes.len() - 3];
+    assert!(matches!(
+        save_file::load(&mut &cut[..]),
+        Err(LoadError::Truncated)
+    ));
+
+    let mut version = bytes.clone();
+    version[4] = 9;
+    assert!(matches!(
+        save_file::load(&mut version.as_slice()),
+        Err(LoadError::UnsupportedVersion(9))
+    ));
+}
+
+#[test]
+fn rejects_a_board_with_wrong_colour_counts() {
+    let config = PuzzleConfig::new(2, 1, 2);
+    assert_eq!(
+        save_file::check_cord(&vec![0x0101, 0x0202, 0x0002], config),
+        Err(CordError::ColorCount {
+            water: 2,
+            count: 3,
+            expected: 2
+        })
+    );
+    assert_eq!(
+        save_file::check_cord(&vec![0x0101, 0x0202, 0], config),
+        Ok(())
+    );
+}
+
+/// 처음 파일: 1줄 4칸, u32 cord의 판과 지금 위치가 마지막인 한 줄기 history
+fn first_save_file(history: &[[u32; 4]]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&4u32.to_le_bytes());
+    let push_cord = |bytes: &mut Vec<u8>, cord: &[u32; 4]| {
+        for waters in cord {
+            bytes.extend_from_slice(&waters.to_le_bytes());
+        }
+    };
+    push_cord(&mut bytes, history.last().unwrap());
+    bytes.extend_from_slice(&(history.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(history.len() as u32 - 1).to_le_bytes());
+    for cord in history {
+        push_cord(&mut bytes, cord);
+    }
+    bytes
+}
+
+#[test]
+fn rejects_history_nodes_more_than_one_pour_apart() {
+    // RRBB|BBRR|....|....
+    let initial = [0x0202_0101, 0x0101_0202, 0, 0];
+    // 0에서 2로 BB를 붓는다.
+    let poured = [0x0101, 0x0101_0202, 0x0202, 0];
+    let solved = [0x0101_0101, 0x0202_0202, 0, 0];
+
+    let (_, _, puzzle) =
+        save_file::load(&mut first_save_file(&[initial, poured]).as_slice()).unwrap();
+    assert_eq!(puzzle.step(), 1);
+
+    let error = save_file::load(&mut first_save_file(&[initial, solved]).as_slice()).unwrap_err();
+    assert!(
+        matches!(&error, LoadError::InvalidHistory(e) if e.contains("node 1")),
+        "{error}"
+    );
+}
+
+#[test]
+fn rejects_unknown_files() {
+    assert!(matches!(
+        save_file::load(&mut &b"not a save file"[..]),
+        Err(LoadError::NotASaveFile)
+    ));
+}
+
+#[test]
+fn migrates_the_first_save_files() {
+    for bytes in [
+        &include_bytes!("../../save.wp")[..],
+        &include_bytes!("../../egui-main/save.wp")[..],
+    ] {
+        let (n_rows, n_cols, puzzle) = save_file::load(&mut &bytes[..]).unwrap();
+        assert_eq!((n_rows, n_cols), (3, 5));
+        assert_eq!(puzzle.config(), PuzzleConfig::new(13, 2, 4));
+    }
+}
@@ -0,0 +1,135 @@
+//! 슬롯을 저장하고 목록, 미리 보기, 자동 저장이 맞게 동작하는지 확인한다.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use puzzle::{
+    puzzle::Puzzle,
+    slots::{AUTOSAVE_SLOT, SlotManager, check_slot_name, format_age},
+};
+use state::{Waters, beaker::UNKNOWN_WATER, config::PuzzleConfig, level_code::LevelCode};
+
+/// 테스트마다 따로 쓰는 빈 폴더
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("waterpuzzle-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// step 번 부은 판. 무작위 판은 부을 곳이 먼저 없어질 수 있으므로 정해진 판을 쓴다.
+fn played(config: PuzzleConfig, step: usize) -> Puzzle {
+    let mut puzzle = Puzzle::from_level_code(LevelCode::new(config, 1));
+    while puzzle.step() < step {
+        let before = puzzle.step();
+        for index in 0..puzzle.n_beakers() {
+            puzzle.on_left_click(index);
+            if puzzle.step() > before {
+                break;
+            }
+        }
+        assert!(puzzle.step() > before, "no move left");
+    }
+    puzzle
+}
+
+#[test]
+fn save_list_load_and_delete() {
+    let dir = temp_dir("slots");
+    let slots = SlotManager::new(&dir);
+    assert!(slots.list().unwrap().is_empty());
+
+    let plain = played(PuzzleConfig::new(5, 2, 4), 2);
+    let mystery = played(PuzzleConfig::new(6, 2, 4).with_mystery(true), 1);
+    slots.save("first try", 2, 4, &plain).unwrap();
+    slots.save("mystery_1", 2, 4, &mystery).unwrap();
+    fs::write(slots.path("broken"), b"not a save file").unwrap();
+    fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+
+    let mut list = slots.list().unwrap();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    let names: Vec<&str> = list.iter().map(|slot| slot.name.as_str()).collect();
+    assert_eq!(names, ["broken", "first try", "mystery_1"]);
+    assert!(list[0].preview.is_err());
+
+    let preview = list[1].preview.as_ref().unwrap();
+    assert_eq!((preview.n_rows, preview.n_cols), (2, 4));
+    assert_eq!(preview.config, plain.config());
+    assert_eq!(preview.step, 2);
+    assert_eq!(preview.cord, plain.visible_cord());
+    // 미리 보기에서도 가려진 층은 보이지 않는다.
+    let preview = list[2].preview.as_ref().unwrap();
+    let layers = |waters: Waters| (0..8).map(move |order| (waters >> (8 * order)) & 0xFF);
+    assert!(
+        preview
+            .cord
+            .iter()
+            .flat_map(|&waters| layers(waters))
+            .any(|water| water == UNKNOWN_WATER as Waters)
+    );
+
+    let (n_rows, n_cols, loaded) = slots.load("first try").unwrap();
+    assert_eq!((n_rows, n_cols), (2, 4));
+    assert_eq!(loaded.step(), 2);
+    assert_eq!(loaded.visible_cord(), plain.visible_cord());
+
+    slots.delete("first try").unwrap();
+    assert!(slots.load("first try").is_err());
+    assert_eq!(slots.list().unwrap().len(), 2);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn autosave_every_n_pours() {
+    let dir = temp_dir("autosave");
+    let mut slots = SlotManager::new(&dir).with_autosave_interval(3);
+    let config = PuzzleConfig::new(5, 2, 4);
+
+    for step in 0..3 {
+        let puzzle = played(config, step);
+        assert!(!slots.autosave(2, 4, &puzzle).unwrap());
+    }
+    let mut puzzle = played(config, 3);
+    assert!(slots.autosave(2, 4, &puzzle).unwrap());
+    assert!(!slots.autosave(2, 4, &puzzle).unwrap());
+    assert_eq!(slots.load(AUTOSAVE_SLOT).unwrap().2.step(), 3);
+
+    // 되돌려도 붓기의 수는 줄지 않는다.
+    puzzle.undo();
+    assert_eq!(puzzle.n_pours(), 3);
+
+    // 새 판은 처음부터 다시 센다.
+    puzzle.reset();
+    assert!(!slots.autosave(2, 4, &puzzle).unwrap());
+    let puzzle = played(config, 3);
+    assert!(slots.autosave(2, 4, &puzzle).unwrap());
+
+    let mut off = SlotManager::new(&dir).with_autosave_interval(0);
+    assert!(!off.autosave(2, 4, &played(config, 4)).unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn slot_names_and_ages() {
+    assert!(check_slot_name("level 3-b_final").is_ok());
+    assert!(check_slot_name("판 1").is_ok());
+    for bad in ["", "  ", " x", "../x", "a/b", "a.wp", "a\\b"] {
+        assert!(check_slot_name(bad).is_err(), "{bad:?}");
+    }
+    assert!(
+        SlotManager::new(temp_dir("names"))
+            .save("../x", 1, 1, &Puzzle::default())
+            .is_err()
+    );
+
+    let now = SystemTime::now();
+    let ago = |secs| now - Duration::from_secs(secs);
+    assert_eq!(format_age(ago(10), now), "just now");
+    assert_eq!(format_age(ago(300), now), "5 min ago");
+    assert_eq!(format_age(ago(7200), now), "2 h ago");
+    assert_eq!(format_age(ago(3 * 86400), now), "3 days ago");
+    // 시계가 뒤로 간 경우
+    assert_eq!(format_age(now + Duration::from_secs(60), now), "just now");
+}
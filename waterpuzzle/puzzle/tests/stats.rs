@@ -0,0 +1,171 @@
+//! 판의 기록을 모으고, 연승과 크기별 평균을 내고, 통계 파일에 적고 다시 읽는지 확인한다.
+
+use std::{thread, time::Duration};
+
+use puzzle::{
+    puzzle::Puzzle,
+    solver::Strategy,
+    stats::{BoardSize, GameResult, Statistics, StatsError, format_duration},
+};
+use state::config::PuzzleConfig;
+
+fn wait_for_solver(puzzle: &mut Puzzle) {
+    while puzzle.solver_remaining_step().is_empty() {
+        puzzle.check_solver();
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn game(config: PuzzleConfig, won: bool, moves: usize, optimal: Option<usize>) -> GameResult {
+    GameResult {
+        config,
+        won,
+        moves,
+        optimal,
+        elapsed: Duration::from_secs(60 * moves as u64),
+        n_hints: 1,
+        n_undos: 2,
+    }
+}
+
+#[test]
+fn puzzle_tracks_a_game() {
+    let mut puzzle = Puzzle::from_code("RRBB|BBRR|....").unwrap();
+    wait_for_solver(&mut puzzle);
+    assert!(puzzle.finish_game().is_none());
+
+    puzzle.apply_solver_hint();
+    puzzle.undo();
+    while !puzzle.is_completed() {
+        wait_for_solver(&mut puzzle);
+        puzzle.apply_solver_hint();
+    }
+    let result = puzzle.finish_game().unwrap();
+    assert!(result.won);
+    assert_eq!(result.moves, 3);
+    assert_eq!(result.optimal, Some(3));
+    assert_eq!(result.score(), Some(100));
+    // 되돌린 뒤 같은 판에서 다시 본 힌트는 세지 않는다.
+    assert_eq!((result.n_hints, result.n_undos), (3, 1));
+    assert_eq!(result.summary()[0], "Solved in 3 moves (optimal 3)");
+    // 한 판은 한 번만 기록한다.
+    assert!(puzzle.finish_game().is_none());
+    assert!(puzzle.give_up().is_none());
+
+    // 붓지 않은 새 판은 기록하지 않고, 부은 판은 진 판으로 기록한다.
+    puzzle.reset();
+    assert!(puzzle.give_up().is_none());
+    for index in 0..puzzle.n_beakers() {
+        if puzzle.n_pours() == 0 {
+            puzzle.on_left_click(index);
+        }
+    }
+    let result = puzzle.give_up().unwrap();
+    assert!(!result.won);
+    assert_eq!(result.moves, 1);
+    assert_eq!(result.score(), None);
+    assert!(puzzle.give_up().is_none());
+}
+
+#[test]
+fn showing_then_applying_a_hint_counts_once() {
+    let mut puzzle = Puzzle::from_code("RRBB|BBRR|....").unwrap();
+    wait_for_solver(&mut puzzle);
+    puzzle.reset_solver_hint();
+    puzzle.reset_solver_hint();
+    puzzle.apply_solver_hint();
+    wait_for_solver(&mut puzzle);
+    puzzle.apply_solver_hint();
+    let result = puzzle.give_up().unwrap();
+    assert_eq!((result.moves, result.n_hints), (2, 2));
+}
+
+#[test]
+fn only_shortest_strategies_note_optimal() {
+    for (strategy, optimal) in [
+        (Strategy::Fast, None),
+        (Strategy::Exhaustive, Some(3)),
+        (Strategy::Parallel, Some(3)),
+        (Strategy::Bounded, Some(3)),
+    ] {
+        let mut puzzle = Puzzle::from_code("RRBB|BBRR|....").unwrap();
+        puzzle.set_solver_strategy(strategy);
+        while !puzzle.is_completed() {
+            wait_for_solver(&mut puzzle);
+            puzzle.apply_solver_hint();
+        }
+        assert_eq!(puzzle.finish_game().unwrap().optimal, optimal, "{strategy}");
+    }
+}
+
+#[test]
+fn streaks_and_averages_by_size() {
+    let small = PuzzleConfig::new(2, 1, 4);
+    let large = PuzzleConfig::new(5, 2, 4);
+    let mut stats = Statistics::default();
+    assert_eq!((stats.current_streak(), stats.best_streak()), (0, 0));
+    for result in [
+        game(small, true, 4, Some(3)),
+        game(large, true, 20, Some(15)),
+        game(small, true, 6, Some(3)),
+        game(large, false, 7, Some(15)),
+        game(small, true, 3, None),
+    ] {
+        stats.record(result);
+    }
+    assert_eq!((stats.n_played(), stats.n_won()), (5, 4));
+    assert_eq!((stats.current_streak(), stats.best_streak()), (1, 3));
+
+    let by_size = stats.by_size();
+    assert_eq!(by_size.len(), 2);
+    let small_summary = by_size[0];
+    assert_eq!(small_summary.size, BoardSize::from(small));
+    assert_eq!((small_summary.n_played, small_summary.n_won), (3, 3));
+    assert_eq!(small_summary.average_moves, Some(13.0 / 3.0));
+    assert_eq!(small_summary.average_time, Some(Duration::from_secs(260)));
+    // 가장 짧은 수를 아는 판만 점수를 낸다: 75와 50
+    assert_eq!(small_summary.average_score, Some(62.5));
+    assert_eq!((by_size[1].n_played, by_size[1].n_won), (2, 1));
+
+    let lines = stats.summary(large);
+    assert_eq!(lines[0], "Streak 1 (best 3), won 4/5");
+    assert_eq!(
+        lines[1],
+        "7 beakers x 4: won 1/2, avg 20.0 moves in 20:00, avg score 75"
+    );
+    assert_eq!(format_duration(Duration::from_secs(3723)), "1:02:03");
+}
+
+#[test]
+fn statistics_file_round_trip() {
+    let config = PuzzleConfig::new(6, 2, 5).with_mystery(true);
+    let mut stats = Statistics::default();
+    stats.record(game(config, true, 30, None));
+    stats.record(game(PuzzleConfig::default(), false, 2, Some(12)));
+
+    let mut bytes = Vec::new();
+    stats.save(&mut bytes).unwrap();
+    assert_eq!(Statistics::load(&mut bytes.as_slice()).unwrap(), stats);
+
+    let mut corrupt = bytes.clone();
+    *corrupt.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        Statistics::load(&mut corrupt.as_slice()),
+        Err(StatsError::ChecksumMismatch { .. })
+    ));
+    assert!(matches!(
+        Statistics::load(&mut &bytes[..bytes.len() - 1]),
+        Err(StatsError::Truncated)
+    ));
+    assert!(matches!(
+        Statistics::load(&mut &b"WPZL"[..]),
+        Err(StatsError::NotStatistics)
+    ));
+
+    let dir = std::env::temp_dir().join(format!("waterpuzzle-stats-{}", std::process::id()));
+    let path = dir.join("stats.wps");
+    assert_eq!(Statistics::load_from(&path).unwrap(), Statistics::default());
+    stats.save_to(&path).unwrap();
+    assert_eq!(Statistics::load_from(&path).unwrap(), stats);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,28 +1,38 @@
 use std::fmt;
 
-use crate::{MAX_WATERS, Water};
+use crate::{DEFAULT_CAPACITY, MAX_CAPACITY, Water, Waters, config::PourRule, error::PourError};
 
 pub const NO_WATER: Water = 0;
-pub(crate) const EMPTY: u32 = NO_WATER | (NO_WATER << 8) | (NO_WATER << 16) | (NO_WATER << 24);
+/// 가려진 층을 솔버에게 넘길 때 쓰는 색. 어느 색과도 같지 않다. See `State.visible_state()`.
+pub const UNKNOWN_WATER: Water = 0xFF;
+pub(crate) const EMPTY: Waters = NO_WATER as Waters;
 
 #[derive(Clone, Copy, Debug, Eq)]
 pub struct Beaker {
-    waters: u32,
+    waters: Waters,
+    capacity: usize,
+    // i번째 비트가 1이면 i번째 층이 가려져 있다. top과 그 위는 가려지지 않는다.
+    hidden: u8,
     pub n_waters: usize,
     pub h: u32,
 }
 
 impl Beaker {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(capacity: usize) -> Self {
+        assert!(0 < capacity && capacity <= MAX_CAPACITY);
         Self {
             waters: EMPTY,
+            capacity,
+            hidden: 0,
             n_waters: 0,
             h: 0,
         }
     }
-    fn _from_waters(waters: u32) -> Self {
+    fn _from_waters(waters: Waters, capacity: usize) -> Self {
         let mut w = Self {
             waters,
+            capacity,
+            hidden: 0,
             n_waters: 0,
             h: 0,
         };
@@ -33,20 +43,46 @@ impl Beaker {
 
     /// This method should only be used for initialization, such as in `State.random_generate()`. See also `Beaker.push()`.
     pub(crate) fn add_water(&mut self, water: Water) {
-        self.waters |= water << (8 * self.n_waters);
+        self.waters |= (water as Waters) << (8 * self.n_waters);
         self.n_waters += 1;
         self.reset_h()
     }
 
     pub fn water(&self, order: usize) -> Water {
-        (self.waters >> (8 * order)) & 0xFF
+        ((self.waters >> (8 * order)) & 0xFF) as Water
     }
 
-    pub fn waters(&self) -> u32 {
+    pub fn waters(&self) -> Waters {
         self.waters
     }
 
-    pub(crate) fn set_waters(&mut self, waters: u32) {
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_hidden(&self, order: usize) -> bool {
+        self.hidden & (1 << order) != 0
+    }
+
+    pub fn hidden(&self) -> u8 {
+        self.hidden
+    }
+
+    pub fn n_hidden(&self) -> usize {
+        self.hidden.count_ones() as usize
+    }
+
+    /// top 아래의 층을 모두 가린다. "Mystery" 판을 만들 때 쓴다.
+    pub(crate) fn hide_below_top(&mut self) {
+        self.hidden = ((1u16 << self.n_waters.saturating_sub(1)) - 1) as u8;
+    }
+
+    /// 한 번 드러난 층은 다시 가려지지 않으므로, 되돌리기를 해도 이미 본 층만 보인다.
+    pub(crate) fn set_hidden(&mut self, hidden: u8) {
+        self.hidden = hidden;
+    }
+
+    pub(crate) fn set_waters(&mut self, waters: Waters) {
         self.waters = waters;
         self.compute_n_waters();
         self.reset_h();
@@ -56,7 +92,7 @@ impl Beaker {
         if self.n_waters == 0 {
             NO_WATER
         } else {
-            (self.waters >> (8 * (self.n_waters - 1))) & 0xFF
+            self.water(self.n_waters - 1)
         }
     }
 
@@ -76,11 +112,12 @@ impl Beaker {
     }
 
     pub fn n_blanks(&self) -> usize {
-        MAX_WATERS - self.n_waters
+        self.capacity - self.n_waters
     }
 
     pub fn empty(&mut self) {
         self.waters = EMPTY;
+        self.hidden = 0;
         self.n_waters = 0;
         self.h = 0;
     }
@@ -90,49 +127,30 @@ impl Beaker {
     }
 
     pub fn is_full(&self) -> bool {
-        self.n_waters == MAX_WATERS
+        self.n_waters == self.capacity
     }
 
     pub fn is_completed(&self) -> bool {
         let w = self.water(0);
-        w == self.water(1) && w == self.water(2) && w == self.water(3)
+        (1..self.capacity).all(|i| self.water(i) == w)
     }
 
     pub fn can_push(&self, water: Water) -> bool {
-        self.n_waters == 0 || (self.n_waters < MAX_WATERS && self.top_water() == water)
+        self.n_waters == 0 || (self.n_waters < self.capacity && self.top_water() == water)
     }
 
-    /// See alse Beaker.add_water()
-    pub(crate) fn push(&mut self, water: Water, count: usize) -> bool {
-        // assert!(0 < water && 0 < count && count <= MAX_WATERS);
-        if self.n_waters + count > MAX_WATERS {
-            return false;
-        }
-        ////////////////////////////////////////////////////////////////////////////////////////////////
-        // bug check
-        // let copy = self.clone();
-        ////////////////////////////////////////////////////////////////////////////////////////////////
+    /// `Beaker::check_pour()`로 부을 수 있는지 확인한 다음에만 부른다. See alse Beaker.add_water()
+    pub(crate) fn push(&mut self, water: Water, count: usize) {
         if self.n_waters == 0 {
             self.h = 1;
         }
         for _ in 0..count {
-            self.waters |= water << (8 * self.n_waters);
+            self.waters |= (water as Waters) << (8 * self.n_waters);
             self.n_waters += 1;
         }
-        if self.n_waters == MAX_WATERS {
+        if self.n_waters == self.capacity {
             self.h -= 1;
         }
-        ////////////////////////////////////////////////////////////////////////////////////////////////
-        // bug check
-        // let ch = _compute_h(self.waters);
-        // if ch != self.h {
-        //     println!(  "{}", copy);
-        //     println!(  "{} {}", ch, self.h);
-        //     println!(  "{} {}", water, count);
-        // }
-        // assert!(_compute_h(self.waters) == self.h);
-        ////////////////////////////////////////////////////////////////////////////////////////////////
-        true
     }
 
     /// 스택의 위에서부터 최대 max_count 개의 Water를 뽑아낸다. 뽑아내는 Water의 색은 top의 색과 같아야 한다.
@@ -146,7 +164,7 @@ impl Beaker {
         // bug check
         // let copy = self.clone();
         ////////////////////////////////////////////////////////////////////////////////////////////////
-        if self.n_waters < MAX_WATERS {
+        if self.n_waters < self.capacity {
             self.h -= 1;
         }
         let top_water = self.top_water();
@@ -165,47 +183,81 @@ impl Beaker {
         if top_water == curr_water {
             self.h += 1;
         }
+        // 새 top이 드러난다.
+        self.hidden &= ((1u16 << self.n_waters.saturating_sub(1)) - 1) as u8;
         ////////////////////////////////////////////////////////////////////////////////////////////////
         // bug check
-        // let ch = _compute_h(self.waters);
+        // let ch = compute_h(self.waters, self.capacity);
         // if ch != self.h {
         //     println!(  "{}", copy);
         //     println!(  "{} {}", ch, self.h);
         //     println!(  "{} {} {}", curr_water, top_water, count);
         // }
-        // assert!(_compute_h(self.waters) == self.h);
+        // assert!(compute_h(self.waters, self.capacity) == self.h);
         ////////////////////////////////////////////////////////////////////////////////////////////////
         (top_water, count)
     }
 
     /// self의 water를 beaker에 부을 수 있고, 붓고 난 다음 self의 top이 부은 색이 아니면 true, 그렇지 않으면 false
-    pub fn pour_results_different_state(&self, beaker: &Self) -> bool {
-        self.can_pour(beaker) && self.top_count() <= beaker.n_blanks()
+    /// PourRule::SingleUnit에서는 한 칸씩만 옮기므로 부을 수 있기만 하면 true
+    pub fn pour_results_different_state(&self, beaker: &Self, rule: PourRule) -> bool {
+        match rule {
+            PourRule::SingleUnit => self.can_pour(beaker, rule),
+            _ => self.can_pour(beaker, rule) && self.top_count() <= beaker.n_blanks(),
+        }
     }
 
-    pub fn can_pour(&self, beaker: &Self) -> bool {
-        self.n_waters > 0 && beaker.can_push(self.top_water())
+    pub fn can_pour(&self, beaker: &Self, rule: PourRule) -> bool {
+        self.check_pour(beaker, rule).is_ok()
     }
 
-    pub(crate) fn pour(&mut self, beaker: &mut Self) -> bool {
-        if self.can_pour(beaker) {
-            // println!("can pour");
-            let water_count_pair = self.pop(beaker.n_blanks());
-            // println!("{:?}", water_count_pair);
-            beaker.push(water_count_pair.0, water_count_pair.1)
-        } else {
-            false
+    /// self에서 beaker로 부을 수 있으면 옮겨지는 물의 개수, 그렇지 않으면 그 이유
+    pub fn check_pour(&self, beaker: &Self, rule: PourRule) -> Result<usize, PourError> {
+        if self.n_waters == 0 {
+            return Err(PourError::SourceEmpty);
+        }
+        let top_water = self.top_water();
+        if beaker.n_waters > 0 && beaker.top_water() != top_water {
+            return Err(PourError::ColorMismatch {
+                top: beaker.top_water(),
+                incoming: top_water,
+            });
+        }
+        let count = match rule {
+            PourRule::SingleUnit => 1,
+            PourRule::WholeRun => self.top_count().min(beaker.n_blanks()),
+            PourRule::FitWholeRun if self.top_count() <= beaker.n_blanks() => self.top_count(),
+            PourRule::FitWholeRun => 0,
+        };
+        if count == 0 || beaker.is_full() {
+            return Err(PourError::TargetFull);
         }
+        Ok(count)
+    }
+
+    /// self에서 beaker로 부었을 때 옮겨지는 물의 개수. 부을 수 없으면 0
+    pub fn pour_count(&self, beaker: &Self, rule: PourRule) -> usize {
+        self.check_pour(beaker, rule).unwrap_or(0)
+    }
+
+    /// 옮긴 물의 개수를 돌려준다.
+    pub(crate) fn pour(&mut self, beaker: &mut Self, rule: PourRule) -> Result<usize, PourError> {
+        let count = self.check_pour(beaker, rule)?;
+        // println!("can pour");
+        let water_count_pair = self.pop(count);
+        // println!("{:?}", water_count_pair);
+        beaker.push(water_count_pair.0, water_count_pair.1);
+        Ok(water_count_pair.1)
     }
 
     fn compute_n_waters(&mut self) {
-        for i in 0..MAX_WATERS {
-            if ((self.waters >> (i << 3)) & 0xFF) == NO_WATER {
+        for i in 0..self.capacity {
+            if self.water(i) == NO_WATER {
                 self.n_waters = i;
                 return;
             }
         }
-        self.n_waters = MAX_WATERS
+        self.n_waters = self.capacity
     }
 
     pub fn _h(&self) -> u32 {
@@ -213,20 +265,13 @@ impl Beaker {
     }
 
     pub(crate) fn reset_h(&mut self) {
-        self.h = 0;
-        let water0 = (self.waters >> (0 * 8)) & 0xFF;
-        let water1 = (self.waters >> (1 * 8)) & 0xFF;
-        if water0 != water1 {
-            self.h += 1;
-        }
-        let water2 = (self.waters >> (2 * 8)) & 0xFF;
-        if water1 != water2 {
-            self.h += 1;
-        }
-        let water3 = (self.waters >> (3 * 8)) & 0xFF;
-        if water2 != water3 {
-            self.h += 1;
-        }
+        self.h = compute_h(self.waters, self.capacity);
+    }
+}
+
+impl Default for Beaker {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
     }
 }
 
@@ -238,33 +283,26 @@ impl PartialEq for Beaker {
 
 impl fmt::Display for Beaker {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let water0 = (self.waters >> (0 * 8)) & 0xFF;
-        let water1 = (self.waters >> (1 * 8)) & 0xFF;
-        let water2 = (self.waters >> (2 * 8)) & 0xFF;
-        let water3 = (self.waters >> (3 * 8)) & 0xFF;
-        write!(
-            f,
-            "({} {} {} {}; {})",
-            water3, water2, water1, water0, self.h
-        )
+        write!(f, "(")?;
+        for i in (0..self.capacity).rev() {
+            if i + 1 < self.capacity {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", self.water(i))?;
+        }
+        write!(f, "; {})", self.h)
     }
 }
 
-// only for assert!()
-fn _compute_h(waters: u32) -> u32 {
+/// 이웃한 두 층의 색이 다른 경계의 개수. 빈 층(NO_WATER)도 하나의 색으로 센다.
+fn compute_h(waters: Waters, capacity: usize) -> u32 {
     let mut h = 0;
-    let water0 = (waters >> (0 * 8)) & 0xFF;
-    let water1 = (waters >> (1 * 8)) & 0xFF;
-    if water0 != water1 {
-        h += 1;
-    }
-    let water2 = (waters >> (2 * 8)) & 0xFF;
-    if water1 != water2 {
-        h += 1;
-    }
-    let water3 = (waters >> (3 * 8)) & 0xFF;
-    if water2 != water3 {
-        h += 1;
+    for i in 1..capacity {
+        let lower = (waters >> (8 * (i - 1))) & 0xFF;
+        let upper = (waters >> (8 * i)) & 0xFF;
+        if lower != upper {
+            h += 1;
+        }
     }
     h
 }
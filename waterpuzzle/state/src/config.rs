@@ -0,0 +1,148 @@
+use std::{
+    error::Error,
+    fmt,
+    io::{Read, Write},
+};
+
+use crate::{DEFAULT_CAPACITY, MAX_CAPACITY};
+
+/// 한 층은 8비트이고 0은 NO_WATER, 0xFF는 UNKNOWN_WATER이므로 색은 254개까지 쓸 수 있다.
+pub const MAX_COLORS: usize = 254;
+
+/// 색마다 빈 비커가 하나씩 있으면 빈 비커를 더 둘 까닭이 없으므로 빈 비커도 MAX_COLORS개까지 쓸 수 있다.
+pub const MAX_EMPTY: usize = MAX_COLORS;
+
+/// 한 번 부을 때 물을 얼마나 옮기는지 정하는 규칙
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PourRule {
+    /// top과 같은 색의 물을 받는 비커의 빈 칸만큼 옮긴다.
+    #[default]
+    WholeRun,
+    /// 한 번에 한 칸만 옮긴다. (ball sort)
+    SingleUnit,
+    /// top과 같은 색의 물이 모두 들어갈 때만 붓는다.
+    FitWholeRun,
+}
+
+impl PourRule {
+    pub const ALL: [PourRule; 3] = [Self::WholeRun, Self::SingleUnit, Self::FitWholeRun];
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Self::WholeRun => 0,
+            Self::SingleUnit => 1,
+            Self::FitWholeRun => 2,
+        }
+    }
+
+    pub fn from_u32(x: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.to_u32() == x)
+    }
+}
+
+impl fmt::Display for PourRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::WholeRun => "Whole run",
+            Self::SingleUnit => "Single unit",
+            Self::FitWholeRun => "Only if it fits",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// 퍼즐의 모양을 정하는 설정. 비커의 개수는 n_colors + n_empty 이다.
+/// Deserialize는 is_valid()를 확인한다. See `serialize.rs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PuzzleConfig {
+    /// 색의 개수. 색마다 capacity 개의 물이 있으므로 처음에 가득 찬 비커의 개수와 같다.
+    pub n_colors: usize,
+    /// 처음에 비어 있는 비커의 개수
+    pub n_empty: usize,
+    /// 비커 하나에 들어가는 물의 개수
+    pub capacity: usize,
+    /// true이면 비커마다 top만 보이고, 그 아래 층은 위의 물을 부어 낸 다음에 드러난다.
+    pub mystery: bool,
+    pub pour_rule: PourRule,
+}
+
+impl PuzzleConfig {
+    pub fn new(n_colors: usize, n_empty: usize, capacity: usize) -> Self {
+        Self {
+            n_colors,
+            n_empty,
+            capacity,
+            mystery: false,
+            pour_rule: PourRule::WholeRun,
+        }
+    }
+
+    pub fn with_mystery(mut self, mystery: bool) -> Self {
+        self.mystery = mystery;
+        self
+    }
+
+    /// 빈 비커 2개와 기본 용량으로 n_beakers 개의 비커를 채우는 설정. is_valid()가 아니면 None
+    pub fn with_n_beakers(n_beakers: usize) -> Option<Self> {
+        let n_colors = n_beakers.checked_sub(2)?;
+        Some(Self::new(n_colors, 2, DEFAULT_CAPACITY)).filter(|config| config.is_valid())
+    }
+
+    pub fn with_pour_rule(mut self, pour_rule: PourRule) -> Self {
+        self.pour_rule = pour_rule;
+        self
+    }
+
+    pub fn n_beakers(&self) -> usize {
+        self.n_colors + self.n_empty
+    }
+
+    pub fn is_valid(&self) -> bool {
+        0 < self.n_colors
+            && self.n_colors <= MAX_COLORS
+            && self.n_empty <= MAX_EMPTY
+            && 0 < self.capacity
+            && self.capacity <= MAX_CAPACITY
+    }
+
+    pub fn save<W: Write>(&self, bw: &mut W) -> Result<(), Box<dyn Error>> {
+        bw.write_all(&(self.n_colors as u32).to_le_bytes())?;
+        bw.write_all(&(self.n_empty as u32).to_le_bytes())?;
+        bw.write_all(&(self.capacity as u32).to_le_bytes())?;
+        bw.write_all(&(self.mystery as u32).to_le_bytes())?;
+        bw.write_all(&self.pour_rule.to_u32().to_le_bytes())?;
+        Ok(())
+    }
+
+    pub fn load<R: Read>(br: &mut R) -> Result<Self, Box<dyn Error>> {
+        let mut buf = [0; 4];
+        br.read_exact(&mut buf)?;
+        let n_colors = u32::from_le_bytes(buf) as usize;
+        br.read_exact(&mut buf)?;
+        let n_empty = u32::from_le_bytes(buf) as usize;
+        br.read_exact(&mut buf)?;
+        let capacity = u32::from_le_bytes(buf) as usize;
+        br.read_exact(&mut buf)?;
+        let mystery = u32::from_le_bytes(buf) != 0;
+        br.read_exact(&mut buf)?;
+        let pour_rule = u32::from_le_bytes(buf);
+        let pour_rule =
+            PourRule::from_u32(pour_rule).ok_or(format!("invalid pour rule {pour_rule}"))?;
+
+        let config = Self::new(n_colors, n_empty, capacity)
+            .with_mystery(mystery)
+            .with_pour_rule(pour_rule);
+        if !config.is_valid() {
+            return Err(format!("invalid puzzle config {config:?}").into());
+        }
+        Ok(config)
+    }
+}
+
+impl Default for PuzzleConfig {
+    fn default() -> Self {
+        Self::new(13, 2, DEFAULT_CAPACITY)
+    }
+}
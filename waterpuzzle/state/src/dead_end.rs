@@ -0,0 +1,71 @@
+//! 목표에 닿을 수 없는 상태(dead end)를 탐색 없이, 또는 아주 작은 탐색으로 알아낸다.
+//! 여기의 검사는 모두 보수적이다. dead라고 하면 반드시 풀 수 없지만, dead가 아니라고 해서 풀 수 있는 것은 아니다.
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::{moves::Move, state::State};
+
+/// is_dead()가 닫힌 영역을 찾으며 살펴보는 상태의 최대 개수
+pub const CLOSED_REGION_LIMIT: usize = 64;
+
+impl State {
+    /// 목표(`is_search_goal()`)에 닿을 수 없음이 증명되면 true.
+    /// - 부을 곳이 하나도 없다.
+    /// - 갈 수 있는 상태가 CLOSED_REGION_LIMIT개 이하이고 그중에 목표가 없다. 같은 물을 두 비커 사이에서 주고받기만 하는 경우이다.
+    pub fn is_dead(&self) -> bool {
+        if self.is_search_goal() {
+            return false;
+        }
+        !self.has_any_pour() || self.is_closed_region()
+    }
+
+    /// 부은 결과가 dead인 붓기들
+    pub fn dead_moves(&self) -> Vec<Move> {
+        (0..self.n_beakers())
+            .flat_map(|from| self.dead_moves_from(from))
+            .collect()
+    }
+
+    /// from에서 붓는 붓기 중 부은 결과가 dead인 것들
+    pub fn dead_moves_from(&self, from: usize) -> Vec<Move> {
+        (0..self.n_beakers())
+            .filter_map(|to| self.move_of(from, to).ok())
+            .filter(|mv| {
+                let mut next = self.clone();
+                next.apply(*mv).is_ok() && next.is_dead()
+            })
+            .collect()
+    }
+
+    fn has_any_pour(&self) -> bool {
+        let n = self.n_beakers();
+        (0..n).any(|i| (0..n).any(|j| i != j && self.can_pour(i, j)))
+    }
+
+    /// 여기서 갈 수 있는 상태를 모두 살펴보았는데 목표가 없으면 true.
+    /// 살펴본 상태가 CLOSED_REGION_LIMIT개를 넘으면 알 수 없으므로 false
+    fn is_closed_region(&self) -> bool {
+        let mut seen = HashSet::from([self.clone()]);
+        let mut queue = VecDeque::from([self.clone()]);
+        while let Some(state) = queue.pop_front() {
+            for i in 0..state.n_beakers() {
+                for j in 0..state.n_beakers() {
+                    if i == j || !state.can_pour(i, j) {
+                        continue;
+                    }
+                    let mut next = state.clone();
+                    let _ = next.pour(i, j);
+                    if seen.contains(&next) {
+                        continue;
+                    }
+                    if next.is_search_goal() || seen.len() >= CLOSED_REGION_LIMIT {
+                        return false;
+                    }
+                    seen.insert(next.clone());
+                    queue.push_back(next);
+                }
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,45 @@
+use std::{error::Error, fmt};
+
+use crate::{Water, notation::water_char};
+
+/// 물을 부을 수 없는 이유
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PourError {
+    /// 같은 비커에 부으려고 했다.
+    SameBeaker,
+    /// 붓는 비커가 비어 있다.
+    SourceEmpty,
+    /// 받는 비커에 빈 칸이 없다. PourRule::FitWholeRun에서는 같은 색의 물이 모두 들어갈 칸이 없다.
+    TargetFull,
+    /// 받는 비커의 top 색과 붓는 물의 색이 다르다.
+    ColorMismatch { top: Water, incoming: Water },
+    /// 비커 번호가 비커의 개수보다 크다.
+    IndexOutOfRange,
+}
+
+impl fmt::Display for PourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SameBeaker => write!(f, "Cannot pour into the same beaker"),
+            Self::SourceEmpty => write!(f, "The beaker is empty"),
+            Self::TargetFull => write!(f, "The target beaker has no room"),
+            Self::ColorMismatch { top, incoming } => {
+                write!(f, "Cannot pour ")?;
+                write_water(f, *incoming)?;
+                write!(f, " onto ")?;
+                write_water(f, *top)
+            }
+            Self::IndexOutOfRange => write!(f, "No such beaker"),
+        }
+    }
+}
+
+impl Error for PourError {}
+
+/// 글자로 적은 판과 같은 이름으로 색을 쓴다. See `notation::water_char()`.
+fn write_water(f: &mut fmt::Formatter<'_>, water: Water) -> fmt::Result {
+    match water_char(water) {
+        Some(c) => write!(f, "{c}"),
+        None => write!(f, "({water})"),
+    }
+}
@@ -0,0 +1,58 @@
+//! 탐색 표에 State 대신 넣는 작은 키.
+
+use crate::{Cord, Waters};
+
+/// Packed 키 하나의 u64 개수. 한 층이 8비트이므로 (비커의 개수 × 용량)이 8 × KEY_WORDS 이하인 판이 들어간다.
+pub const KEY_WORDS: usize = 8;
+
+/// 정렬한 비커들의 층을 빈틈없이 이어 붙인 키. 비커의 순서만 다른 상태는 같은 키가 된다. See `State::key()`.
+/// 비커의 벡터를 들고 있는 State보다 훨씬 작고 해시가 빠르다. `State::from_key()`로 상태를 다시 만든다.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StateKey {
+    Packed([u64; KEY_WORDS]),
+    /// Packed에 들어가지 않는 큰 판
+    Cord(Box<[Waters]>),
+}
+
+impl StateKey {
+    /// 비커마다 8 × capacity 비트씩 차례로 이어 붙인다.
+    pub(crate) fn pack(cord: &Cord, capacity: usize) -> Self {
+        let width = 8 * capacity;
+        if cord.len() * width > 64 * KEY_WORDS {
+            return Self::Cord(cord.as_slice().into());
+        }
+        let mut words = [0; KEY_WORDS];
+        for (k, &waters) in cord.iter().enumerate() {
+            let (word, shift) = (k * width / 64, k * width % 64);
+            words[word] |= waters << shift;
+            if shift + width > 64 {
+                words[word + 1] |= waters >> (64 - shift);
+            }
+        }
+        Self::Packed(words)
+    }
+
+    /// pack()의 반대. n_beakers와 capacity는 pack()할 때와 같아야 한다.
+    pub(crate) fn unpack(&self, n_beakers: usize, capacity: usize) -> Cord {
+        let words = match self {
+            Self::Packed(words) => words,
+            Self::Cord(cord) => return cord.to_vec(),
+        };
+        let width = 8 * capacity;
+        let mask = if width == 64 {
+            Waters::MAX
+        } else {
+            (1 << width) - 1
+        };
+        (0..n_beakers)
+            .map(|k| {
+                let (word, shift) = (k * width / 64, k * width % 64);
+                let mut waters = words[word] >> shift;
+                if shift + width > 64 {
+                    waters |= words[word + 1] << (64 - shift);
+                }
+                waters & mask
+            })
+            .collect()
+    }
+}
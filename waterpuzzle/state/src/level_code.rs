@@ -0,0 +1,106 @@
+use std::{fmt, str::FromStr};
+
+use crate::config::{PourRule, PuzzleConfig};
+
+const DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const MYSTERY_FLAG: char = 'M';
+const SINGLE_UNIT_FLAG: char = 'S';
+const FIT_WHOLE_RUN_FLAG: char = 'F';
+
+/// 판을 다시 만들 수 있는 짧은 코드. config와 seed로 이루어진다.
+/// 문자열로는 `{n_colors}-{n_empty}-{capacity}-{seed}` 꼴이고 seed는 36진수로 쓴다. 예: `13-2-4-3J8K0Q2ZP1`
+/// 기본이 아닌 설정은 끝에 `-`와 글자를 붙인다. "Mystery"는 `M`, PourRule::SingleUnit은 `S`,
+/// PourRule::FitWholeRun은 `F`이다. 예: `13-2-4-3J8K0Q2ZP1-MS`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelCode {
+    pub config: PuzzleConfig,
+    pub seed: u64,
+}
+
+impl LevelCode {
+    pub fn new(config: PuzzleConfig, seed: u64) -> Self {
+        Self { config, seed }
+    }
+}
+
+impl fmt::Display for LevelCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut seed = self.seed;
+        let mut digits = Vec::new();
+        loop {
+            digits.push(DIGITS[(seed % 36) as usize]);
+            seed /= 36;
+            if seed == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+        write!(
+            f,
+            "{}-{}-{}-{}",
+            self.config.n_colors,
+            self.config.n_empty,
+            self.config.capacity,
+            String::from_utf8(digits).unwrap()
+        )?;
+        let mut flags = String::new();
+        if self.config.mystery {
+            flags.push(MYSTERY_FLAG);
+        }
+        match self.config.pour_rule {
+            PourRule::WholeRun => {}
+            PourRule::SingleUnit => flags.push(SINGLE_UNIT_FLAG),
+            PourRule::FitWholeRun => flags.push(FIT_WHOLE_RUN_FLAG),
+        }
+        if !flags.is_empty() {
+            write!(f, "-{flags}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LevelCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.trim().split('-').collect();
+        let (n_colors, n_empty, capacity, seed, flags) = match fields[..] {
+            [n_colors, n_empty, capacity, seed] => (n_colors, n_empty, capacity, seed, ""),
+            [n_colors, n_empty, capacity, seed, flags] if !flags.is_empty() => {
+                (n_colors, n_empty, capacity, seed, flags)
+            }
+            _ => return Err(format!("level code must have 4 or 5 fields: {s}")),
+        };
+        // 같은 판이 한 가지 코드로만 적히도록 글자를 거듭 쓰거나 PourRule을 둘 쓰면 틀린 코드로 본다.
+        let mut mystery = false;
+        let mut pour_rule = None;
+        for flag in flags.chars() {
+            match flag {
+                MYSTERY_FLAG if mystery => {
+                    return Err(format!("repeated flag {flag} in level code"));
+                }
+                MYSTERY_FLAG => mystery = true,
+                SINGLE_UNIT_FLAG | FIT_WHOLE_RUN_FLAG if pour_rule.is_some() => {
+                    return Err(format!("more than one pour rule in level code: {flags}"));
+                }
+                SINGLE_UNIT_FLAG => pour_rule = Some(PourRule::SingleUnit),
+                FIT_WHOLE_RUN_FLAG => pour_rule = Some(PourRule::FitWholeRun),
+                _ => return Err(format!("invalid flag {flag} in level code")),
+            }
+        }
+        let pour_rule = pour_rule.unwrap_or_default();
+        let number = |field: &str| {
+            field
+                .parse::<usize>()
+                .map_err(|_| format!("invalid number {field} in level code"))
+        };
+        let config = PuzzleConfig::new(number(n_colors)?, number(n_empty)?, number(capacity)?)
+            .with_mystery(mystery)
+            .with_pour_rule(pour_rule);
+        if !config.is_valid() {
+            return Err(format!("invalid puzzle config {config:?}"));
+        }
+        let seed = u64::from_str_radix(seed, 36).map_err(|_| format!("invalid seed {seed}"))?;
+        Ok(Self::new(config, seed))
+    }
+}
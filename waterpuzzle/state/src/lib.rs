@@ -1,10 +1,23 @@
 // pub const NONE: i32 = -1;
 pub const UNDEFINED_U32: u32 = u32::MAX;
 pub const INFINITY_USIZE: usize = usize::MAX;
-pub const MAX_WATERS: usize = 4;
+/// 비커 하나에 들어가는 물의 기본 개수
+pub const DEFAULT_CAPACITY: usize = 4;
+/// 비커 하나에 들어갈 수 있는 물의 최대 개수. 한 층이 8비트이므로 u64에는 8층까지 들어간다.
+pub const MAX_CAPACITY: usize = 8;
 
 pub type Water = u32;
-pub type Cord = Vec<u32>;
+pub type Waters = u64;
+pub type Cord = Vec<Waters>;
 
 pub mod beaker;
+pub mod config;
+pub mod dead_end;
+pub mod error;
+pub mod key;
+pub mod level_code;
+pub mod moves;
+pub mod notation;
+#[cfg(feature = "serde")]
+mod serialize;
 pub mod state;
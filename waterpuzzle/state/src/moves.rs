@@ -0,0 +1,104 @@
+//! 붓기 한 번을 나타내는 `Move`. 솔버의 답, 힌트, 다시 보기가 모두 이것을 쓴다.
+
+use std::fmt;
+
+use crate::{Water, error::PourError, notation::water_char, state::State};
+
+/// from 비커에서 to 비커로 color 물을 count 개 옮긴다.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    pub color: Water,
+    pub count: usize,
+}
+
+impl fmt::Display for Move {
+    /// 예: `3->5 RR`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}->{} ", self.from, self.to)?;
+        for _ in 0..self.count {
+            match water_char(self.color) {
+                Some(c) => write!(f, "{c}")?,
+                None => write!(f, "({})", self.color)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    /// 가장 짧은 경로를 바꾸지 않고 뺄 수 있는 붓기를 뺀 나머지 붓기들.
+    /// 빼는 붓기는 모두 지금 상태와 같은 상태가 되거나, 남은 붓기 중 하나와 같은 상태가 된다.
+    /// (비커의 순서만 다른 상태는 같은 상태이다.)
+    /// 1. 완성된 비커에서 붓기. 그 색은 모두 이 비커에 있으므로 빈 비커에만 부을 수 있고, 그 색은 다른 색과 섞이지
+    ///    않으므로 옮겨도 다른 붓기를 돕지 못한다.
+    /// 2. 한 색만 든 비커의 물을 모두 빈 비커로 옮기기. 비커의 순서만 바뀐다.
+    /// 3. 내용이 같은 비커가 여럿이면 그중 처음 것만 붓는 쪽, 받는 쪽으로 쓴다. 빈 비커가 여럿일 때가 대표적이다.
+    pub fn useful_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        let n = self.n_beakers();
+        // first[k]: k와 내용이 같은 비커 중 가장 앞의 것, next[k]: k 다음으로 내용이 같은 비커
+        let mut first: Vec<usize> = (0..n).collect();
+        let mut next = vec![None; n];
+        for j in 0..n {
+            let beaker = self.beaker(j);
+            if let Some(k) = (0..j).find(|&k| {
+                first[k] == k
+                    && self.beaker(k).waters() == beaker.waters()
+                    && self.beaker(k).hidden() == beaker.hidden()
+            }) {
+                first[j] = k;
+                next[k] = next[k].or(Some(j));
+            }
+        }
+        let mut pairs = Vec::new();
+        for i in (0..n).filter(|&i| first[i] == i) {
+            let source = self.beaker(i);
+            if source.is_full() && source.is_completed() {
+                continue;
+            }
+            // i와 내용이 같은 비커로는 i 다음 것에만 붓는다.
+            pairs.extend(
+                (0..n)
+                    .filter(|&j| j != i && (first[j] == j || (first[j] == i && next[i] == Some(j))))
+                    .map(|j| (i, j)),
+            );
+        }
+        pairs
+            .into_iter()
+            .filter_map(move |(i, j)| self.move_of(i, j).ok())
+            .filter(move |mv| {
+                let source = self.beaker(mv.from);
+                let moves_whole_beaker =
+                    mv.count == source.n_waters && source.top_count() == source.n_waters;
+                !(moves_whole_beaker && self.beaker(mv.to).is_empty())
+            })
+    }
+}
+
+/// 상태들의 경로를 붓기의 목록으로 바꾼다. 경로의 상태는 비커의 순서가 달라도 같은 상태로 보므로,
+/// 각 단계에서 다음 상태와 같아지는 붓기를 찾아 initial의 비커 순서대로 적는다.
+/// 이웃하지 않은 두 상태가 있으면 None
+pub fn moves_of_path(path: &[State]) -> Option<Vec<Move>> {
+    let mut state = path.first()?.clone();
+    let mut moves = Vec::with_capacity(path.len() - 1);
+    for next in &path[1..] {
+        let mv = state.move_to(next)?;
+        state.apply(mv).ok()?;
+        moves.push(mv);
+    }
+    Some(moves)
+}
+
+/// initial에서 moves를 차례로 붓고 initial을 포함한 모든 상태를 돌려준다.
+pub fn replay(initial: &State, moves: &[Move]) -> Result<Vec<State>, PourError> {
+    let mut path = Vec::with_capacity(moves.len() + 1);
+    path.push(initial.clone());
+    let mut state = initial.clone();
+    for mv in moves {
+        state.apply(*mv)?;
+        path.push(state.clone());
+    }
+    Ok(path)
+}
@@ -0,0 +1,188 @@
+//! 판을 글자로 적는 방법. 비커마다 토큰 하나를 `|`로 잇고, 토큰은 아래층부터 위층 순서로 색 글자를 쓴다.
+//! 빈 층은 `.`로 쓴다. 예: 용량이 4인 비커 3개는 `RRGG|GGRR|....`
+//! 글자가 모자라는 색은 `(번호)`로 쓴다. 색이 n개이면 앞의 n개 색(`R`, `B`, `Y`, ...)을 빠짐없이 써야 한다.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::{
+    Cord, MAX_CAPACITY, Water,
+    beaker::{Beaker, NO_WATER},
+    config::{MAX_COLORS, PuzzleConfig},
+    state::State,
+};
+
+pub const BEAKER_SEPARATOR: char = '|';
+pub const BLANK: char = '.';
+
+/// 색 1부터 차례로 쓰는 글자. 앞의 14개는 GUI의 팔레트(빨강, 파랑, 노랑, 초록, ...)를 따른다.
+const WATER_CHARS: &[u8] = b"RBYGMTOPLIKNAZCDEFHJQSUVWXabcdefghijklmnopqrstuvwxyz";
+
+pub fn water_char(water: Water) -> Option<char> {
+    let index = (water as usize).checked_sub(1)?;
+    WATER_CHARS.get(index).map(|&c| c as char)
+}
+
+/// 글자가 없는 색은 `(번호)`
+fn water_name(water: Water) -> String {
+    water_char(water).map_or(format!("({water})"), String::from)
+}
+
+pub fn water_of_char(c: char) -> Option<Water> {
+    WATER_CHARS
+        .iter()
+        .position(|&x| x as char == c)
+        .map(|index| index as Water + 1)
+}
+
+/// 비커 하나의 토큰. 예: `RRG.`
+pub(crate) fn beaker_token(beaker: &Beaker) -> String {
+    let mut token = String::new();
+    for order in 0..beaker.capacity() {
+        match beaker.water(order) {
+            NO_WATER => token.push(BLANK),
+            water => match water_char(water) {
+                Some(c) => token.push(c),
+                None => token.push_str(&format!("({water})")),
+            },
+        }
+    }
+    token
+}
+
+/// 용량을 모르는 cord를 글자로 적는다. 빈 층은 적지 않는다. 예: `RRGG|GR|`
+pub fn cord_to_text(cord: &Cord) -> String {
+    let mut text = String::new();
+    for (i, &waters) in cord.iter().enumerate() {
+        if i > 0 {
+            text.push(BEAKER_SEPARATOR);
+        }
+        let mut beaker = Beaker::new(MAX_CAPACITY);
+        beaker.set_waters(waters);
+        text.push_str(beaker_token(&beaker).trim_end_matches(BLANK));
+    }
+    text
+}
+
+/// cord_to_text()의 반대. 끝의 빈 층은 적어도 된다.
+pub fn cord_from_text(text: &str) -> Result<Cord, String> {
+    let mut cord = Cord::new();
+    if text.trim().is_empty() {
+        return Ok(cord);
+    }
+    for token in text.trim().split(BEAKER_SEPARATOR) {
+        let token = token.trim().trim_end_matches(BLANK);
+        let waters = parse_beaker_token(token)?;
+        if waters.len() > MAX_CAPACITY || waters.contains(&NO_WATER) {
+            return Err(format!("invalid beaker {token}"));
+        }
+        let mut beaker = Beaker::new(MAX_CAPACITY);
+        for water in waters {
+            beaker.add_water(water);
+        }
+        cord.push(beaker.waters());
+    }
+    Ok(cord)
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, beaker) in self.beakers.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{BEAKER_SEPARATOR}")?;
+            }
+            write!(f, "{}", beaker_token(beaker))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for State {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut waters_of_beakers = Vec::new();
+        for token in s.trim().split(BEAKER_SEPARATOR) {
+            waters_of_beakers.push(parse_beaker_token(token.trim())?);
+        }
+
+        let capacity = waters_of_beakers[0].len();
+        if capacity == 0 || capacity > MAX_CAPACITY {
+            return Err(format!(
+                "beaker capacity must be 1..={MAX_CAPACITY}: {capacity}"
+            ));
+        }
+        let mut counts = BTreeMap::new();
+        for (i, waters) in waters_of_beakers.iter().enumerate() {
+            if waters.len() != capacity {
+                return Err(format!(
+                    "beaker {i} has {} layers, expected {capacity}",
+                    waters.len()
+                ));
+            }
+            let n_waters = waters.iter().take_while(|&&w| w != NO_WATER).count();
+            if waters[n_waters..].iter().any(|&w| w != NO_WATER) {
+                return Err(format!("beaker {i} has water above a blank"));
+            }
+            for &water in &waters[..n_waters] {
+                *counts.entry(water).or_insert(0) += 1;
+            }
+        }
+        if let Some((water, count)) = counts.iter().find(|&(_, &count)| count != capacity) {
+            return Err(format!(
+                "colour {} appears {count} times, expected {capacity}",
+                water_name(*water)
+            ));
+        }
+
+        let n_colors = counts.len();
+        if n_colors == 0 {
+            return Err("no water in the board".to_string());
+        }
+        // 설정은 색 1..=n_colors를 쓴다. 번호를 다시 붙이면 적은 글자와 다른 색이 되므로 받지 않는다.
+        if let Some(missing) = (1..=n_colors as Water).find(|water| !counts.contains_key(water)) {
+            return Err(format!(
+                "{n_colors} colours must be the first {n_colors} colour letters, but {} is missing",
+                water_name(missing)
+            ));
+        }
+        let config = PuzzleConfig::new(n_colors, waters_of_beakers.len() - n_colors, capacity);
+        let beakers = waters_of_beakers
+            .iter()
+            .map(|waters| {
+                let mut beaker = Beaker::new(capacity);
+                for &water in waters.iter().take_while(|&&w| w != NO_WATER) {
+                    beaker.add_water(water);
+                }
+                beaker
+            })
+            .collect();
+        Ok(State::from_beakers(beakers, config))
+    }
+}
+
+pub(crate) fn parse_beaker_token(token: &str) -> Result<Vec<Water>, String> {
+    let mut waters = Vec::new();
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        let water = match c {
+            BLANK => NO_WATER,
+            '(' => {
+                let mut number = String::new();
+                loop {
+                    match chars.next() {
+                        Some(')') => break,
+                        Some(c) => number.push(c),
+                        None => return Err(format!("missing ) after ({number} in {token}")),
+                    }
+                }
+                match number.parse::<Water>() {
+                    Ok(water) if 0 < water && water as usize <= MAX_COLORS => water,
+                    _ => return Err(format!("invalid colour ({number}) in {token}")),
+                }
+            }
+            c => water_of_char(c).ok_or(format!("invalid colour {c} in {token}"))?,
+        };
+        waters.push(water);
+    }
+    Ok(waters)
+}
@@ -0,0 +1,143 @@
+//! `serde` 기능을 켜면 쓰는 Serialize, Deserialize. 사람이 읽고 고칠 수 있도록 비커는 글자 토큰으로 적는다.
+//! 예: `{"config": {...}, "beakers": [{"waters": "RRGG"}, {"waters": "GR..", "hidden": 1}]}`
+//! 읽을 때는 설정과 비커의 모양이 맞는지 확인한다.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+
+use crate::{
+    MAX_CAPACITY,
+    beaker::{Beaker, NO_WATER},
+    config::{PourRule, PuzzleConfig},
+    notation::{beaker_token, parse_beaker_token},
+    state::State,
+};
+
+#[derive(Serialize, Deserialize)]
+struct BeakerRecord {
+    waters: String,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    hidden: u8,
+}
+
+fn is_zero(hidden: &u8) -> bool {
+    *hidden == 0
+}
+
+impl Serialize for Beaker {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BeakerRecord {
+            waters: beaker_token(self),
+            hidden: self.hidden(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Beaker {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = BeakerRecord::deserialize(deserializer)?;
+        let waters = parse_beaker_token(&record.waters).map_err(D::Error::custom)?;
+        let capacity = waters.len();
+        if capacity == 0 || capacity > MAX_CAPACITY {
+            return Err(D::Error::custom(format!(
+                "beaker capacity must be 1..={MAX_CAPACITY}: {}",
+                record.waters
+            )));
+        }
+        let n_waters = waters.iter().take_while(|&&w| w != NO_WATER).count();
+        if waters[n_waters..].iter().any(|&w| w != NO_WATER) {
+            return Err(D::Error::custom(format!(
+                "water above a blank: {}",
+                record.waters
+            )));
+        }
+        // top과 그 위는 가려지지 않는다.
+        if (record.hidden as usize) >> n_waters.saturating_sub(1) != 0 {
+            return Err(D::Error::custom(format!(
+                "hidden layers {:#b} are not below the top of {}",
+                record.hidden, record.waters
+            )));
+        }
+        let mut beaker = Beaker::new(capacity);
+        for &water in &waters[..n_waters] {
+            beaker.add_water(water);
+        }
+        beaker.set_hidden(record.hidden);
+        Ok(beaker)
+    }
+}
+
+#[derive(Deserialize)]
+struct ConfigRecord {
+    n_colors: usize,
+    n_empty: usize,
+    capacity: usize,
+    #[serde(default)]
+    mystery: bool,
+    #[serde(default)]
+    pour_rule: PourRule,
+}
+
+impl<'de> Deserialize<'de> for PuzzleConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = ConfigRecord::deserialize(deserializer)?;
+        let config = Self::new(record.n_colors, record.n_empty, record.capacity)
+            .with_mystery(record.mystery)
+            .with_pour_rule(record.pour_rule);
+        if !config.is_valid() {
+            return Err(D::Error::custom(format!(
+                "invalid puzzle config {config:?}"
+            )));
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateRecord<B> {
+    config: PuzzleConfig,
+    beakers: B,
+}
+
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StateRecord {
+            config: self.config(),
+            beakers: &self.beakers,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let StateRecord { config, beakers } =
+            StateRecord::<Vec<Beaker>>::deserialize(deserializer)?;
+        if beakers.len() != config.n_beakers() {
+            return Err(D::Error::custom(format!(
+                "{} beakers, expected {}",
+                beakers.len(),
+                config.n_beakers()
+            )));
+        }
+        for (i, beaker) in beakers.iter().enumerate() {
+            if beaker.capacity() != config.capacity {
+                return Err(D::Error::custom(format!(
+                    "beaker {i} has {} layers, expected {}",
+                    beaker.capacity(),
+                    config.capacity
+                )));
+            }
+            if let Some(water) = (0..beaker.n_waters)
+                .map(|order| beaker.water(order))
+                .find(|&water| water as usize > config.n_colors)
+            {
+                return Err(D::Error::custom(format!(
+                    "beaker {i} has colour ({water}) out of {} colours",
+                    config.n_colors
+                )));
+            }
+        }
+        Ok(Self::from_beakers(beakers, config))
+    }
+}
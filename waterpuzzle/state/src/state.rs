@@ -1,59 +1,96 @@
-use std::hash::Hash;
+use std::{hash::Hash, sync::OnceLock};
 
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-use crate::{beaker::Beaker, *};
-
-const N_EMPTY_BEAKER: usize = 2;
+use crate::{
+    beaker::{Beaker, NO_WATER, UNKNOWN_WATER},
+    config::{PourRule, PuzzleConfig},
+    error::PourError,
+    key::StateKey,
+    moves::Move,
+    *,
+};
 
 #[derive(Default, Clone, Debug, Eq)]
 pub struct State {
     pub beakers: Vec<Beaker>,
-    sorted_cord: Cord,
+    config: PuzzleConfig,
+    // 처음 쓸 때 만든다. 붓기마다 만들면 비싸므로 비커가 바뀌면 비우기만 한다. See `State::canonical_cord()`.
+    canonical_cord: OnceLock<Cord>,
     pub h: u32,
 }
 
 impl State {
-    pub fn new(n_beakers: usize) -> Self {
-        Self::from_beakers(vec![Beaker::new(); n_beakers])
+    pub fn new(config: PuzzleConfig) -> Self {
+        Self::from_beakers(
+            vec![Beaker::new(config.capacity); config.n_beakers()],
+            config,
+        )
     }
 
-    pub fn from_beakers(beakers: Vec<Beaker>) -> Self {
-        let sorted_cord = Self::sorted_cord_of(&beakers);
+    pub fn from_beakers(beakers: Vec<Beaker>, config: PuzzleConfig) -> Self {
+        assert_eq!(beakers.len(), config.n_beakers());
         let h = Self::h_of(&beakers);
         Self {
             beakers,
-            sorted_cord,
+            config,
+            canonical_cord: OnceLock::new(),
             h,
         }
     }
 
-    pub fn _from_cord(cord: &Cord) -> Self {
-        let mut state = Self::new(cord.len());
+    pub fn _from_cord(cord: &Cord, config: PuzzleConfig) -> Self {
+        let mut state = Self::new(config);
         state.apply_cord(cord);
         state
     }
 
+    pub fn config(&self) -> PuzzleConfig {
+        self.config
+    }
+
     pub fn n_beakers(&self) -> usize {
         self.beakers.len()
     }
 
+    /// 비커 하나에 들어가는 물의 개수. 모든 비커의 용량은 같다.
+    pub fn capacity(&self) -> usize {
+        self.config.capacity
+    }
+
     pub fn n_colors(&self) -> usize {
-        self.n_beakers() - N_EMPTY_BEAKER
+        self.config.n_colors
+    }
+
+    pub fn pour_rule(&self) -> PourRule {
+        self.config.pour_rule
+    }
+
+    /// 임의의 seed로 물을 섞어 채우고, 같은 판을 다시 만들 수 있도록 그 seed를 돌려준다.
+    pub fn random_generate(&mut self) -> u64 {
+        let seed = rand::rng().random();
+        self.generate_with_seed(seed);
+        seed
+    }
+
+    /// 같은 seed와 config에서는 항상 같은 판이 만들어진다.
+    pub fn generate_with_seed(&mut self, seed: u64) {
+        self.generate_with(&mut ChaCha8Rng::seed_from_u64(seed));
     }
 
-    pub fn random_generate(&mut self) {
+    fn generate_with<R: Rng>(&mut self, rng: &mut R) {
         self.clear();
+        let capacity = self.capacity();
         let mut water_used = vec![0; self.n_colors() + 1];
         let mut remaining_waters: Vec<usize> = (1..=self.n_colors()).collect();
         let mut remaining_beakers: Vec<usize> = (0..self.n_colors()).collect();
 
-        let mut rng = rand::rng();
         while !remaining_waters.is_empty() {
             let water_index = rng.random_range(0..remaining_waters.len());
             let water = remaining_waters[water_index];
             water_used[water] += 1;
-            if water_used[water] == MAX_WATERS {
+            if water_used[water] == capacity {
                 remaining_waters.remove(water_index);
             }
 
@@ -64,7 +101,12 @@ impl State {
                 remaining_beakers.remove(beaker_index);
             }
         }
-        self.sorted_cord = Self::sorted_cord_of(&self.beakers);
+        if self.config.mystery {
+            for beaker in self.beakers.iter_mut() {
+                beaker.hide_below_top();
+            }
+        }
+        self.canonical_cord = OnceLock::new();
         self.h = Self::h_of(&self.beakers);
         //////////////////////////////////////////////////////////////////////////
         // bug
@@ -79,7 +121,7 @@ impl State {
             beaker.empty()
         }
         self.h = 0;
-        self.sorted_cord = Self::sorted_cord_of(&self.beakers);
+        self.canonical_cord = OnceLock::new();
     }
 
     pub fn is_completed(&self) -> bool {
@@ -87,6 +129,55 @@ impl State {
         self.h == 0
     }
 
+    /// 솔버가 찾는 목표. 완성되었거나, visible_state()에서 가려져 있던 층이 top으로 드러난 상태이다.
+    /// 드러난 층의 색은 부어 보기 전에는 알 수 없으므로 거기서 탐색을 멈춘다.
+    pub fn is_search_goal(&self) -> bool {
+        self.is_completed()
+            || self
+                .beakers
+                .iter()
+                .any(|beaker| beaker.top_water() == UNKNOWN_WATER)
+    }
+
+    pub fn n_hidden(&self) -> usize {
+        self.beakers.iter().map(|beaker| beaker.n_hidden()).sum()
+    }
+
+    /// 비커마다 가려진 층의 비트 마스크. See `Beaker.hidden()`.
+    pub fn hidden_mask(&self) -> Vec<u8> {
+        self.beakers.iter().map(|beaker| beaker.hidden()).collect()
+    }
+
+    pub fn apply_hidden_mask(&mut self, mask: &[u8]) {
+        for (hidden, beaker) in mask.iter().zip(self.beakers.iter_mut()) {
+            beaker.set_hidden(*hidden);
+        }
+    }
+
+    /// 플레이어가 볼 수 있는 것만 남긴 상태. 가려진 층은 UNKNOWN_WATER가 된다.
+    /// 솔버에게는 이 상태를 넘겨서 가려진 색을 쓰지 못하게 한다.
+    pub fn visible_state(&self) -> Self {
+        if self.n_hidden() == 0 {
+            return self.clone();
+        }
+        let beakers = self
+            .beakers
+            .iter()
+            .map(|beaker| {
+                let mut visible = Beaker::new(beaker.capacity());
+                for order in 0..beaker.n_waters {
+                    if beaker.is_hidden(order) {
+                        visible.add_water(UNKNOWN_WATER);
+                    } else {
+                        visible.add_water(beaker.water(order));
+                    }
+                }
+                visible
+            })
+            .collect();
+        Self::from_beakers(beakers, self.config)
+    }
+
     pub fn beaker(&self, index: usize) -> &Beaker {
         &self.beakers[index]
     }
@@ -96,13 +187,30 @@ impl State {
         self.beakers.iter().any(|src_beaker| {
             self.beakers.iter().any(|dst_beaker| {
                 !std::ptr::eq(src_beaker, dst_beaker)
-                    && src_beaker.pour_results_different_state(dst_beaker)
+                    && src_beaker.pour_results_different_state(dst_beaker, self.pour_rule())
             })
         })
     }
 
+    /// See `State::check_pour()`.
     pub fn can_pour(&self, i: usize, j: usize) -> bool {
-        self.beakers[i].can_pour(&self.beakers[j])
+        self.check_pour(i, j).is_ok()
+    }
+
+    /// i에서 j로 부었을 때 옮겨지는 물의 개수. 부을 수 없으면 0. See `State::check_pour()`.
+    pub fn pour_count(&self, i: usize, j: usize) -> usize {
+        self.check_pour(i, j).unwrap_or(0)
+    }
+
+    /// i에서 j로 부을 수 있으면 옮겨지는 물의 개수, 그렇지 않으면 그 이유
+    pub fn check_pour(&self, i: usize, j: usize) -> Result<usize, PourError> {
+        if i >= self.n_beakers() || j >= self.n_beakers() {
+            return Err(PourError::IndexOutOfRange);
+        }
+        if i == j {
+            return Err(PourError::SameBeaker);
+        }
+        self.beakers[i].check_pour(&self.beakers[j], self.pour_rule())
     }
 
     pub fn print_beakers(&self) {
@@ -112,12 +220,15 @@ impl State {
         }
     }
 
-    pub fn pour(&mut self, i: usize, j: usize) -> bool {
+    /// i에서 j로 붓고 옮긴 물의 개수를 돌려준다. 부을 수 없으면 상태는 바뀌지 않는다.
+    pub fn pour(&mut self, i: usize, j: usize) -> Result<usize, PourError> {
+        self.check_pour(i, j)?;
         ///////////////////////////////////////////////////////////////////////////////////////////
         // bug
         //     println!("----- {} {}", self.h, Self::h_of(&self.beakers));
         // self.print_beakers();
         ///////////////////////////////////////////////////////////////////////////////////////////
+        let rule = self.pour_rule();
         let prev_h_at_ij = self.beakers[i].h + self.beakers[j].h;
         let poured = if i < j {
             ///////////////////////////////////////////////////////////////////////////////////////////
@@ -129,7 +240,7 @@ impl State {
             // }
             ///////////////////////////////////////////////////////////////////////////////////////////
             let (src, dst) = self.beakers.split_at_mut(j);
-            src[i].pour(&mut dst[0])
+            src[i].pour(&mut dst[0], rule)
         } else {
             ///////////////////////////////////////////////////////////////////////////////////////////
             // bug
@@ -140,12 +251,12 @@ impl State {
             // }
             ///////////////////////////////////////////////////////////////////////////////////////////
             let (dst, src) = self.beakers.split_at_mut(i);
-            src[0].pour(&mut dst[j])
+            src[0].pour(&mut dst[j], rule)
         };
-        if poured {
+        if poured.is_ok() {
             self.h -= prev_h_at_ij;
             self.h += self.beakers[i].h + self.beakers[j].h;
-            self.sorted_cord = Self::sorted_cord_of(&self.beakers);
+            self.canonical_cord = OnceLock::new();
             //////////////////////////////////////////////////////////////////////////
             // bug
             // if self.h != Self::h_of(&self.beakers) {
@@ -164,6 +275,45 @@ impl State {
         poured
     }
 
+    /// i에서 j로 붓는 Move. 부을 수 없으면 그 이유
+    pub fn move_of(&self, i: usize, j: usize) -> Result<Move, PourError> {
+        let count = self.check_pour(i, j)?;
+        Ok(Move {
+            from: i,
+            to: j,
+            color: self.beakers[i].top_water(),
+            count,
+        })
+    }
+
+    /// 한 번 부어서 next와 같아지는 Move. 그런 붓기가 없으면 None
+    /// 색과 비커의 순서만 다른 상태도 같은 상태(`PartialEq`)로 본다. 비커마다 같아야 하면 move_to_cord()를 쓴다.
+    pub fn move_to(&self, next: &Self) -> Option<Move> {
+        self.find_move(|state| state == next)
+    }
+
+    /// 한 번 부어서 cord()가 next와 똑같아지는 Move. 그런 붓기가 없으면 None
+    pub fn move_to_cord(&self, next: &Cord) -> Option<Move> {
+        self.find_move(|state| state.cord() == *next)
+    }
+
+    fn find_move(&self, mut is_next: impl FnMut(&Self) -> bool) -> Option<Move> {
+        let n = self.n_beakers();
+        (0..n)
+            .flat_map(|i| (0..n).map(move |j| (i, j)))
+            .filter_map(|(i, j)| self.move_of(i, j).ok())
+            .find(|mv| {
+                let mut state = self.clone();
+                state.apply(*mv).is_ok() && is_next(&state)
+            })
+    }
+
+    /// mv.from에서 mv.to로 붓는다. color와 count는 기록용이다.
+    /// 가려진 층이 있는 판에서는 실제로 옮겨지는 개수가 mv.count보다 많을 수 있다.
+    pub fn apply(&mut self, mv: Move) -> Result<usize, PourError> {
+        self.pour(mv.from, mv.to)
+    }
+
     pub fn pourable_beaker_index(&mut self, i: usize) -> Option<usize> {
         let mut h = UNDEFINED_U32;
         // split은 세 값 중 하나를을 가진다.
@@ -180,13 +330,13 @@ impl State {
             if self.can_pour(i, j) {
                 match split {
                     Some(false) => {
-                        if top_count <= self.beaker(j).n_blanks() && self.beaker(j).h > h {
+                        if self.pour_count(i, j) == top_count && self.beaker(j).h > h {
                             h = self.beaker(j).h;
                             target = Some(j);
                         }
                     }
                     Some(true) => {
-                        split = Some(top_count > self.beaker(j).n_blanks());
+                        split = Some(self.pour_count(i, j) < top_count);
                         if !split.unwrap() || self.beaker(j).h > h {
                             h = self.beaker(j).h;
                             target = Some(j);
@@ -195,7 +345,7 @@ impl State {
                     None => {
                         h = self.beaker(j).h;
                         target = Some(j);
-                        split = Some(top_count > self.beaker(j).n_blanks());
+                        split = Some(self.pour_count(i, j) < top_count);
                     }
                 }
             }
@@ -207,17 +357,200 @@ impl State {
         self.beakers.iter().map(|beaker| beaker.waters()).collect()
     }
 
-    fn sorted_cord_of(beakers: &[Beaker]) -> Cord {
+    /// 비커의 순서와 색의 이름을 모두 무시한 상태의 표현. Eq와 Hash, key()가 이것을 쓴다.
+    /// 처음 부를 때 canonical_cord_of()로 만들어 두고, 비커가 바뀔 때까지 다시 쓴다.
+    fn canonical_cord(&self) -> &Cord {
+        self.canonical_cord
+            .get_or_init(|| Self::canonical_cord_of(&self.beakers))
+    }
+
+    /// 색을 이름과 관계없는 무리로 나누고(`refine_colors()`), 무리의 번호를 색의 새 이름으로 삼아 비커들을
+    /// 정렬한다. 더 나눌 수 없는데 색이 여럿 남은 무리가 있으면 그 색들을 하나씩 떼어 본 값 중 가장 작은 것을
+    /// 쓴다. 그래서 색과 비커의 순서만 다른 상태는 언제나 같은 값이 되고, 값이 같은 두 상태는 언제나 색과
+    /// 비커의 순서만 다르다. NO_WATER와 UNKNOWN_WATER는 바꾸지 않는다.
+    ///
+    /// 무리 나누기 한 번은 층의 개수를 L이라 할 때 O(L log L)이고 색의 개수만큼까지 되풀이한다.
+    /// 떼어 보기는 가장 나쁜 경우 k개 색의 무리에서 k!가지를 본다. 서로 바꾸어도 같은 색(`is_symmetric()`)은
+    /// 건너뛰므로 다 채운 비커처럼 정말 바꿀 수 있는 색에서는 한 가지만 보고, 무작위로 만든 판은 대개 첫 나누기에서
+    /// 모든 색이 갈린다. 색의 모양이 모두 같지만 서로 바꿀 수 없는, 일부러 만든 판만 k!에 가까워진다.
+    fn canonical_cord_of(beakers: &[Beaker]) -> Cord {
+        let mut colors: Vec<Water> = beakers
+            .iter()
+            .flat_map(|beaker| (0..beaker.n_waters).map(|order| beaker.water(order)))
+            .filter(|&water| water != NO_WATER && water != UNKNOWN_WATER)
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+        let mut classes = [0; 256];
+        for &water in &colors {
+            classes[water as usize] = 1;
+        }
+        Self::refine_colors(beakers, &colors, &mut classes);
+        Self::canonical_cord_from(beakers, &colors, classes)
+    }
+
+    /// classes[색]을 색의 새 이름으로 쓴 canonical cord. 색이 둘 이상인 무리가 있으면 그 색들을 하나씩 떼어 본다.
+    fn canonical_cord_from(beakers: &[Beaker], colors: &[Water], classes: [u8; 256]) -> Cord {
+        let mut sizes = [0; 256];
+        for &water in colors {
+            sizes[classes[water as usize] as usize] += 1;
+        }
+        let Some(tied) = (1..=255).find(|&class| sizes[class as usize] > 1) else {
+            let mut cord: Cord = beakers
+                .iter()
+                .map(|beaker| Self::relabel(beaker, &mut classes.clone(), &mut 0))
+                .collect();
+            cord.sort();
+            return cord;
+        };
+        let mut best: Option<Cord> = None;
+        let mut tried: Vec<Water> = Vec::new();
+        for &water in colors
+            .iter()
+            .filter(|&&water| classes[water as usize] == tied)
+        {
+            // 이미 떼어 본 색과 서로 바꾸어도 같은 상태이면 떼어 봐도 같은 값이 나온다.
+            if tried
+                .iter()
+                .any(|&other| Self::is_symmetric(beakers, other, water))
+            {
+                continue;
+            }
+            tried.push(water);
+            // 떼어 낸 색이 무리의 앞에 오고, 무리의 나머지와 뒤의 무리들은 번호가 하나씩 밀린다.
+            let mut split = classes;
+            for &other in colors {
+                let class = &mut split[other as usize];
+                if *class > tied || (*class == tied && other != water) {
+                    *class += 1;
+                }
+            }
+            Self::refine_colors(beakers, colors, &mut split);
+            let cord = Self::canonical_cord_from(beakers, colors, split);
+            if best.as_ref().is_none_or(|best| cord < *best) {
+                best = Some(cord);
+            }
+        }
+        best.unwrap()
+    }
+
+    /// 색들을 이름과 관계없이 무리로 나눈다. classes[색]은 1부터의 무리 번호이고, 무리를 나누기만 한다.
+    /// 색마다 그 색이 든 비커의 모양(층마다 그 색인지, 다른 색의 무리, 빈 층, 가려진 층)을 모아 비교하고,
+    /// 무리가 더 나뉘지 않을 때까지 되풀이한다.
+    fn refine_colors(beakers: &[Beaker], colors: &[Water], classes: &mut [u8; 256]) {
+        let mut n_classes = 0;
+        loop {
+            // (색, 그 색이 든 비커의 모양). 모양은 층마다 16비트이다.
+            let mut shapes: Vec<(Water, u128)> = Vec::new();
+            for beaker in beakers {
+                let first = shapes.len();
+                for order in 0..beaker.n_waters {
+                    let water = beaker.water(order);
+                    if water == NO_WATER
+                        || water == UNKNOWN_WATER
+                        || shapes[first..].iter().any(|shape| shape.0 == water)
+                    {
+                        continue;
+                    }
+                    let shape = (0..beaker.n_waters).fold(0, |shape, order| {
+                        let layer = match beaker.water(order) {
+                            NO_WATER => 0,
+                            UNKNOWN_WATER => 1,
+                            other if other == water => 2,
+                            other => classes[other as usize] as u128 + 2,
+                        };
+                        shape | layer << (16 * order)
+                    });
+                    shapes.push((water, shape));
+                }
+            }
+            shapes.sort_unstable();
+            let mut groups: Vec<(u8, &[(Water, u128)])> = shapes
+                .chunk_by(|a, b| a.0 == b.0)
+                .map(|chunk| (classes[chunk[0].0 as usize], chunk))
+                .collect();
+            // 색의 이름은 빼고 비교한다.
+            let same = |a: &(u8, &[(Water, u128)]), b: &(u8, &[(Water, u128)])| {
+                a.0.cmp(&b.0).then_with(|| {
+                    a.1.iter()
+                        .map(|shape| shape.1)
+                        .cmp(b.1.iter().map(|shape| shape.1))
+                })
+            };
+            groups.sort_by(same);
+            let mut class = 0;
+            for (i, group) in groups.iter().enumerate() {
+                if i == 0 || same(&groups[i - 1], group).is_ne() {
+                    class += 1;
+                }
+                classes[group.1[0].0 as usize] = class;
+            }
+            if class == n_classes || class as usize == colors.len() {
+                return;
+            }
+            n_classes = class;
+        }
+    }
+
+    /// 색 a와 b를 서로 바꾸어도 비커들이 (순서를 빼고) 그대로이면 true
+    fn is_symmetric(beakers: &[Beaker], a: Water, b: Water) -> bool {
+        let mut labels: [u8; 256] = std::array::from_fn(|water| water as u8);
+        labels.swap(a as usize, b as usize);
         let mut cord: Cord = beakers.iter().map(|beaker| beaker.waters()).collect();
+        let mut swapped: Cord = beakers
+            .iter()
+            .map(|beaker| Self::relabel(beaker, &mut labels, &mut 0))
+            .collect();
         cord.sort();
-        cord
+        swapped.sort();
+        cord == swapped
+    }
+
+    /// 색이 처음 나오는 차례대로 next부터 이름을 붙인다. labels[원래 색]이 새 색이고 0은 아직 정해지지 않았다는 뜻이다.
+    fn relabel(beaker: &Beaker, labels: &mut [u8; 256], next: &mut u8) -> Waters {
+        let mut waters = beaker.waters();
+        for order in 0..beaker.n_waters {
+            let water = beaker.water(order);
+            if water == NO_WATER || water == UNKNOWN_WATER {
+                continue;
+            }
+            let label = &mut labels[water as usize];
+            if *label == 0 {
+                *label = *next;
+                *next += 1;
+            }
+            waters &= !(0xFF << (8 * order));
+            waters |= (*label as Waters) << (8 * order);
+        }
+        waters
+    }
+
+    /// 탐색 표에 넣는 키. canonical cord를 묶은 것이므로 키가 같은 것과 상태가 같은 것(Eq)이 언제나 일치한다.
+    /// 비커의 순서나 색의 이름만 다른 상태는 같은 키가 된다.
+    pub fn key(&self) -> StateKey {
+        StateKey::pack(self.canonical_cord(), self.capacity())
+    }
+
+    /// key()로 만든 키에서 상태를 다시 만든다. 비커는 key()에서 정렬한 순서로 놓이고 색은 바꾼 이름을 쓴다.
+    /// 다시 만든 상태는 처음 상태와 같다(Eq).
+    pub fn from_key(key: &StateKey, config: PuzzleConfig) -> Self {
+        let beakers = key
+            .unpack(config.n_beakers(), config.capacity)
+            .into_iter()
+            .map(|waters| {
+                let mut beaker = Beaker::new(config.capacity);
+                beaker.set_waters(waters);
+                beaker
+            })
+            .collect();
+        Self::from_beakers(beakers, config)
     }
 
     pub fn apply_cord(&mut self, cord: &Cord) {
         for (waters, beaker) in cord.iter().zip(self.beakers.iter_mut()) {
             beaker.set_waters(*waters)
         }
-        self.sorted_cord = Self::sorted_cord_of(&self.beakers);
+        self.canonical_cord = OnceLock::new();
         self.h = Self::h_of(&self.beakers);
     }
 
@@ -228,12 +561,12 @@ impl State {
 
 impl PartialEq for State {
     fn eq(&self, other: &Self) -> bool {
-        self.sorted_cord == other.sorted_cord
+        self.canonical_cord() == other.canonical_cord()
     }
 }
 
 impl Hash for State {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.sorted_cord.hash(state);
+        self.canonical_cord().hash(state);
     }
 }
@@ -0,0 +1,19 @@
+//! PuzzleConfig를 만드는 함수가 올바른 설정만 돌려주는지 확인한다.
+
+use state::{DEFAULT_CAPACITY, config::PuzzleConfig};
+
+#[test]
+fn with_n_beakers_needs_a_colour() {
+    assert_eq!(
+        PuzzleConfig::with_n_beakers(15),
+        Some(PuzzleConfig::default())
+    );
+    assert_eq!(
+        PuzzleConfig::with_n_beakers(3),
+        Some(PuzzleConfig::new(1, 2, DEFAULT_CAPACITY))
+    );
+    // 빈 비커 2개를 빼면 색이 남지 않는다.
+    for n_beakers in 0..=2 {
+        assert_eq!(PuzzleConfig::with_n_beakers(n_beakers), None, "{n_beakers}");
+    }
+}
@@ -0,0 +1,112 @@
+//! 작은 판에서 갈 수 있는 상태를 모두 살펴보고, is_dead()가 풀 수 있는 상태를 dead라고 하지 않는지 확인한다.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use state::{
+    config::{PourRule, PuzzleConfig},
+    state::State,
+};
+
+/// (색의 개수, 빈 비커의 개수, 용량)
+const SMALL_BOARDS: [(usize, usize, usize); 4] = [(3, 1, 3), (3, 1, 4), (4, 1, 3), (3, 2, 3)];
+
+/// initial에서 갈 수 있는 모든 상태와, 그 상태에서 목표에 닿을 수 있는지 여부
+fn solvability(initial: &State) -> HashMap<State, bool> {
+    let mut next_states: HashMap<State, Vec<State>> = HashMap::new();
+    let mut queue = VecDeque::from([initial.clone()]);
+    next_states.insert(initial.clone(), Vec::new());
+    while let Some(state) = queue.pop_front() {
+        let mut nexts = Vec::new();
+        for i in 0..state.n_beakers() {
+            for j in 0..state.n_beakers() {
+                let mut next = state.clone();
+                if next.pour(i, j).is_ok() {
+                    if !next_states.contains_key(&next) {
+                        next_states.insert(next.clone(), Vec::new());
+                        queue.push_back(next.clone());
+                    }
+                    nexts.push(next);
+                }
+            }
+        }
+        next_states.insert(state, nexts);
+    }
+
+    let mut solvable: HashSet<State> = next_states
+        .keys()
+        .filter(|state| state.is_search_goal())
+        .cloned()
+        .collect();
+    loop {
+        let before = solvable.len();
+        for (state, nexts) in &next_states {
+            if nexts.iter().any(|next| solvable.contains(next)) {
+                solvable.insert(state.clone());
+            }
+        }
+        if solvable.len() == before {
+            break;
+        }
+    }
+    next_states
+        .into_keys()
+        .map(|state| {
+            let is_solvable = solvable.contains(&state);
+            (state, is_solvable)
+        })
+        .collect()
+}
+
+#[test]
+fn dead_states_are_unsolvable() {
+    let mut n_dead = 0;
+    for (n_colors, n_empty, capacity) in SMALL_BOARDS {
+        for rule in PourRule::ALL {
+            for seed in 0..4 {
+                let config = PuzzleConfig::new(n_colors, n_empty, capacity).with_pour_rule(rule);
+                let mut initial = State::new(config);
+                initial.generate_with_seed(seed);
+                for (state, is_solvable) in solvability(&initial) {
+                    if state.is_dead() {
+                        assert!(!is_solvable, "{state} ({rule}) is solvable");
+                        n_dead += 1;
+                    }
+                }
+            }
+        }
+    }
+    assert!(n_dead > 0);
+}
+
+#[test]
+fn dead_moves_lead_to_dead_states() {
+    let mut initial = State::new(PuzzleConfig::new(3, 1, 3));
+    initial.generate_with_seed(0);
+    for state in solvability(&initial).into_keys() {
+        let dead_moves = state.dead_moves();
+        for i in 0..state.n_beakers() {
+            let expected: Vec<_> = dead_moves
+                .iter()
+                .copied()
+                .filter(|mv| mv.from == i)
+                .collect();
+            assert_eq!(state.dead_moves_from(i), expected, "{state}: {i}");
+            for j in 0..state.n_beakers() {
+                let Ok(mv) = state.move_of(i, j) else {
+                    continue;
+                };
+                let mut next = state.clone();
+                next.apply(mv).unwrap();
+                assert_eq!(dead_moves.contains(&mv), next.is_dead(), "{state}: {mv}");
+            }
+        }
+    }
+}
+
+#[test]
+fn stuck_state_is_dead() {
+    let state: State = "RB|BR".parse().unwrap();
+    assert!(state.is_dead());
+    let state: State = "RB|BR|..".parse().unwrap();
+    assert!(!state.is_dead());
+}
@@ -0,0 +1,83 @@
+//! StateKey에서 다시 만든 상태가 원래 상태와 같은지 확인한다.
+
+use state::{config::PuzzleConfig, key::StateKey, state::State};
+
+fn generate(n_colors: usize, n_empty: usize, capacity: usize, seed: u64) -> State {
+    let mut state = State::new(PuzzleConfig::new(n_colors, n_empty, capacity));
+    state.generate_with_seed(seed);
+    state
+}
+
+#[test]
+fn from_key_restores_the_same_state() {
+    // 용량 3, 5, 7은 비커가 u64의 경계에 걸친다.
+    for (n_colors, n_empty, capacity) in [(5, 2, 4), (8, 2, 3), (10, 2, 5), (7, 2, 7), (6, 2, 8)] {
+        for seed in 0..5 {
+            let state = generate(n_colors, n_empty, capacity, seed);
+            let key = state.key();
+            assert!(matches!(key, StateKey::Packed(_)), "{state}");
+            let restored = State::from_key(&key, state.config());
+            assert_eq!(restored, state);
+            assert_eq!(restored.key(), key);
+            assert_eq!(restored.h, state.h);
+        }
+    }
+}
+
+#[test]
+fn same_state_has_same_key() {
+    let state = generate(5, 2, 4, 0);
+    let mut beakers = state.beakers.clone();
+    beakers.reverse();
+    let reversed = State::from_beakers(beakers, state.config());
+    assert_eq!(reversed.key(), state.key());
+
+    let mut poured = state.clone();
+    let mv = poured.useful_moves().next().unwrap();
+    poured.apply(mv).unwrap();
+    assert_ne!(poured.key(), state.key());
+}
+
+#[test]
+fn colour_permuted_states_share_a_key() {
+    let state = generate(5, 2, 4, 3);
+    // 빨강과 파랑을 맞바꾼다.
+    let swapped: String = state
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            'R' => 'B',
+            'B' => 'R',
+            c => c,
+        })
+        .collect();
+    let swapped: State = swapped.parse().unwrap();
+    assert_eq!(swapped, state);
+    assert_eq!(swapped.key(), state.key());
+
+    // 모든 색이 모든 층에 한 번씩 있어 색만으로는 구별되지 않는 판
+    let state: State = "RBY|YRB|BYR|...".parse().unwrap();
+    for names in ["RYB", "BRY", "BYR", "YRB", "YBR"] {
+        let permuted: String = state
+            .to_string()
+            .chars()
+            .map(|c| match "RBY".find(c) {
+                Some(i) => names.as_bytes()[i] as char,
+                None => c,
+            })
+            .collect();
+        let permuted: State = permuted.parse().unwrap();
+        assert_eq!(permuted, state, "{names}");
+        assert_eq!(permuted.key(), state.key(), "{names}");
+        assert_eq!(State::from_key(&permuted.key(), permuted.config()), state);
+    }
+}
+
+#[test]
+fn big_board_falls_back_to_cord() {
+    // 비커 14개 × 40비트는 Packed의 64 × KEY_WORDS비트를 넘는다.
+    let state = generate(12, 2, 5, 0);
+    let key = state.key();
+    assert!(matches!(key, StateKey::Cord(_)));
+    assert_eq!(State::from_key(&key, state.config()), state);
+}
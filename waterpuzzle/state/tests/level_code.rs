@@ -0,0 +1,49 @@
+//! 판 코드를 읽고 적으며, 쓸 수 없는 설정의 코드를 거르는지 확인한다.
+
+use state::{
+    config::{MAX_COLORS, MAX_EMPTY, PuzzleConfig},
+    level_code::LevelCode,
+};
+
+#[test]
+fn code_round_trip() {
+    for config in [
+        PuzzleConfig::new(5, 2, 4),
+        PuzzleConfig::new(6, 2, 5).with_mystery(true),
+        PuzzleConfig::new(MAX_COLORS, MAX_EMPTY, 8),
+    ] {
+        let level_code = LevelCode::new(config, 12345);
+        assert_eq!(level_code.to_string().parse(), Ok(level_code));
+    }
+}
+
+#[test]
+fn rejects_invalid_configs() {
+    for code in [
+        "254-1000000-8-0",
+        "254-255-8-0",
+        "255-2-4-0",
+        "0-2-4-0",
+        "5-2-9-0",
+        "5-2-0-0",
+    ] {
+        assert!(code.parse::<LevelCode>().is_err(), "{code}");
+    }
+    assert!(!PuzzleConfig::new(3, MAX_EMPTY + 1, 4).is_valid());
+}
+
+#[test]
+fn rejects_repeated_or_conflicting_flags() {
+    for code in [
+        "5-2-4-0-MM",
+        "5-2-4-0-SS",
+        "5-2-4-0-SF",
+        "5-2-4-0-FMS",
+        "5-2-4-0-",
+    ] {
+        assert!(code.parse::<LevelCode>().is_err(), "{code}");
+    }
+    for code in ["5-2-4-0-M", "5-2-4-0-MS", "5-2-4-0-F"] {
+        assert!(code.parse::<LevelCode>().is_ok(), "{code}");
+    }
+}
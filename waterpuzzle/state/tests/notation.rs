@@ -0,0 +1,48 @@
+//! 글자로 적은 판을 읽고 다시 적으면 같은 판이 되는지, 적은 색을 그대로 쓰는지 확인한다.
+
+use state::{config::PuzzleConfig, state::State};
+
+#[test]
+fn text_round_trip() {
+    for text in ["RRBB|BBRR|....", "RRB|BBR|...", "RRRB|BBB.|R...|...."] {
+        let state: State = text.parse().unwrap();
+        assert_eq!(state.to_string(), text);
+        assert_eq!(
+            state.to_string().parse::<State>().unwrap().cord(),
+            state.cord()
+        );
+    }
+
+    let mut state = State::new(PuzzleConfig::new(6, 2, 5).with_mystery(true));
+    state.generate_with_seed(7);
+    let loaded: State = state.to_string().parse().unwrap();
+    assert_eq!(loaded.to_string(), state.to_string());
+    assert_eq!((loaded.config().n_colors, loaded.config().n_empty), (6, 2));
+}
+
+#[test]
+fn keeps_the_colours_as_written() {
+    let state: State = "BBRR|RRBB|....".parse().unwrap();
+    assert_eq!(state.to_string(), "BBRR|RRBB|....");
+    let state: State = "BBBB|RRRR|YYYY|....".parse().unwrap();
+    assert_eq!(state.to_string(), "BBBB|RRRR|YYYY|....");
+    assert_eq!(state.config(), PuzzleConfig::new(3, 1, 4));
+}
+
+#[test]
+fn rejects_bad_boards() {
+    for (text, reason) in [
+        ("RRB|BB.|R.R", "above a blank"),
+        ("RRB|BBR|..", "beaker 2 has 2 layers"),
+        ("RRB|BB.|...", "colour R appears 2 times"),
+        ("...|...", "no water"),
+        ("RR?|...", ""),
+        ("BBBB|YYYY|....|....", "R is missing"),
+        ("(60)(60)G|G(60)G|...", "R is missing"),
+        ("(12|...", "missing )"),
+        ("R(1|R..", "missing )"),
+    ] {
+        let e = text.parse::<State>().unwrap_err();
+        assert!(e.contains(reason), "{text}: {e}");
+    }
+}
@@ -0,0 +1,45 @@
+//! 부을 수 없는 까닭마다 맞는 PourError를 돌려주고, 붓지 못하면 상태가 바뀌지 않는지 확인한다.
+
+use state::{config::PourRule, error::PourError, state::State};
+
+/// text의 판을 rule로 붓는 상태
+fn with_rule(text: &str, rule: PourRule) -> State {
+    let parsed: State = text.parse().unwrap();
+    let mut state = State::new(parsed.config().with_pour_rule(rule));
+    state.apply_cord(&parsed.cord());
+    state
+}
+
+#[test]
+fn error_for_each_reason() {
+    let whole_run = with_rule("RRBB|RRB.|B...|....", PourRule::WholeRun);
+    let fit_only = with_rule("RRBB|RRB.|B...|....", PourRule::FitWholeRun);
+    let mixed: State = "RRBR|BBB.|R...|....".parse().unwrap();
+    for (state, i, j, expected) in [
+        (&whole_run, 0, 0, PourError::SameBeaker),
+        (&whole_run, 3, 0, PourError::SourceEmpty),
+        (&whole_run, 2, 0, PourError::TargetFull),
+        (
+            &mixed,
+            2,
+            1,
+            PourError::ColorMismatch {
+                top: 2,
+                incoming: 1,
+            },
+        ),
+        (&fit_only, 0, 1, PourError::TargetFull),
+        (&whole_run, 0, 4, PourError::IndexOutOfRange),
+        (&whole_run, 4, 0, PourError::IndexOutOfRange),
+    ] {
+        assert_eq!(state.check_pour(i, j), Err(expected), "{state}: {i} -> {j}");
+        assert!(!state.can_pour(i, j), "{state}: {i} -> {j}");
+        assert_eq!(state.pour_count(i, j), 0, "{state}: {i} -> {j}");
+
+        let mut poured = state.clone();
+        assert_eq!(poured.pour(i, j), Err(expected), "{state}: {i} -> {j}");
+        assert_eq!(poured.to_string(), state.to_string(), "{state}: {i} -> {j}");
+    }
+    // 같은 붓기도 PourRule::WholeRun에서는 들어가는 만큼 붓는다.
+    assert_eq!(whole_run.check_pour(0, 1), Ok(1));
+}
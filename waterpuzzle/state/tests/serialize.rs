@@ -0,0 +1,70 @@
+//! `serde` 기능: 상태를 JSON으로 적고 다시 읽으면 같은 상태가 되는지, 모양이 맞지 않는 JSON을 거절하는지 확인한다.
+
+use state::{
+    config::{PourRule, PuzzleConfig},
+    notation::{cord_from_text, cord_to_text},
+    state::State,
+};
+
+#[test]
+fn round_trip_keeps_colours_and_hidden_layers() {
+    let config = PuzzleConfig::new(6, 2, 5)
+        .with_mystery(true)
+        .with_pour_rule(PourRule::SingleUnit);
+    let mut state = State::new(config);
+    state.generate_with_seed(3);
+    assert!(state.n_hidden() > 0);
+
+    let json = serde_json::to_string(&state).unwrap();
+    let loaded: State = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.config(), config);
+    assert_eq!(loaded.cord(), state.cord());
+    assert_eq!(loaded.hidden_mask(), state.hidden_mask());
+    assert_eq!(loaded.h, state.h);
+}
+
+#[test]
+fn reads_a_hand_written_state() {
+    let json = r#"{
+        "config": {"n_colors": 2, "n_empty": 1, "capacity": 4},
+        "beakers": [{"waters": "RRBB", "hidden": 3}, {"waters": "BBRR"}, {"waters": "...."}]
+    }"#;
+    let state: State = serde_json::from_str(json).unwrap();
+    assert_eq!(state.to_string(), "RRBB|BBRR|....");
+    assert_eq!(state.hidden_mask(), vec![3, 0, 0]);
+    assert_eq!(state.pour_rule(), PourRule::WholeRun);
+
+    for (bad, reason) in [
+        (
+            r#""n_colors": 0, "n_empty": 1, "capacity": 4"#,
+            "invalid puzzle config",
+        ),
+        (
+            r#""n_colors": 2, "n_empty": 2, "capacity": 4"#,
+            "3 beakers, expected 4",
+        ),
+        (
+            r#""n_colors": 2, "n_empty": 1, "capacity": 5"#,
+            "expected 5",
+        ),
+    ] {
+        let json = json.replace(r#""n_colors": 2, "n_empty": 1, "capacity": 4"#, bad);
+        let e = serde_json::from_str::<State>(&json).unwrap_err();
+        assert!(e.to_string().contains(reason), "{e}");
+    }
+
+    // 맨 위의 층은 가릴 수 없다.
+    let json = json.replace(r#""hidden": 3"#, r#""hidden": 8"#);
+    assert!(serde_json::from_str::<State>(&json).is_err());
+}
+
+#[test]
+fn cord_text_round_trip() {
+    let cord = vec![0x0201_0101, 100, 0];
+    let text = cord_to_text(&cord);
+    assert_eq!(text, "RRRB|(100)|");
+    assert_eq!(cord_from_text(&text).unwrap(), cord);
+    // 끝의 빈 층은 적어도 된다.
+    assert_eq!(cord_from_text("RR..|B.|").unwrap(), vec![0x0101, 0x02, 0]);
+    assert!(cord_from_text("R.R|").is_err());
+}
@@ -0,0 +1,150 @@
+//! 색의 이름과 비커의 순서만 다른 상태가 같은 상태(Eq, Hash, key)가 되고,
+//! 같은 상태는 언제나 색과 비커의 순서만 다른지 모든 색의 순열과 비교해 확인한다.
+//! move_to()는 같은 상태로 가는 붓기를, move_to_cord()는 비커마다 똑같은 판으로 가는 붓기만 찾는지도 확인한다.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, RandomState},
+};
+
+use state::{config::PuzzleConfig, key::StateKey, state::State};
+
+const NAMES: &[u8] = b"RBYG";
+
+/// 처음 상태에서 갈 수 있는 상태들. 비커의 순서만 다른 상태는 하나만 넣는다.
+fn reachable(n_colors: usize, n_empty: usize, capacity: usize, seed: u64) -> Vec<State> {
+    let mut initial = State::new(PuzzleConfig::new(n_colors, n_empty, capacity));
+    initial.generate_with_seed(seed);
+    let sorted_cord = |state: &State| {
+        let mut cord = state.cord();
+        cord.sort();
+        cord
+    };
+    let mut seen = HashSet::from([sorted_cord(&initial)]);
+    let mut states = vec![initial.clone()];
+    let mut queue = VecDeque::from([initial]);
+    while let Some(state) = queue.pop_front() {
+        for i in 0..state.n_beakers() {
+            for j in 0..state.n_beakers() {
+                let mut next = state.clone();
+                if next.pour(i, j).is_ok() && seen.insert(sorted_cord(&next)) {
+                    states.push(next.clone());
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    states
+}
+
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![vec![]];
+    }
+    let mut result = Vec::new();
+    for rest in permutations(n - 1) {
+        for i in 0..n {
+            let mut perm = rest.clone();
+            perm.insert(i, n - 1);
+            result.push(perm);
+        }
+    }
+    result
+}
+
+/// 색 NAMES[k]를 NAMES[perm[k]]로 바꾸고 비커를 거꾸로 놓은 상태
+fn permuted(state: &State, perm: &[usize]) -> State {
+    let text: String = state
+        .to_string()
+        .bytes()
+        .map(|c| match NAMES.iter().position(|&name| name == c) {
+            Some(k) => NAMES[perm[k]] as char,
+            None => c as char,
+        })
+        .collect();
+    let state: State = text.parse().unwrap();
+    let mut beakers = state.beakers.clone();
+    beakers.reverse();
+    State::from_beakers(beakers, state.config())
+}
+
+/// 모든 색의 순열 중 정렬한 비커 글자가 가장 작은 것
+fn brute_force_canonical(state: &State) -> Vec<String> {
+    permutations(state.config().n_colors)
+        .iter()
+        .map(|perm| {
+            let mut beakers: Vec<String> = permuted(state, perm)
+                .to_string()
+                .split('|')
+                .map(str::to_string)
+                .collect();
+            beakers.sort();
+            beakers
+        })
+        .min()
+        .unwrap()
+}
+
+#[test]
+fn permuted_states_are_equal() {
+    let hasher = RandomState::new();
+    for (n_colors, n_empty, capacity, seed) in [(3, 1, 3, 0), (4, 2, 2, 1)] {
+        for state in reachable(n_colors, n_empty, capacity, seed) {
+            for perm in permutations(n_colors) {
+                let other = permuted(&state, &perm);
+                assert_eq!(other, state, "{perm:?}");
+                assert_eq!(hasher.hash_one(&other), hasher.hash_one(&state), "{state}");
+                assert_eq!(other.key(), state.key(), "{state}");
+            }
+        }
+    }
+}
+
+#[test]
+fn equal_states_are_permutations() {
+    let mut n_classes = 0;
+    for (n_colors, n_empty, capacity, seed) in [
+        (3, 1, 3, 0),
+        (3, 2, 3, 2),
+        (4, 1, 3, 0),
+        (4, 2, 3, 1),
+        (4, 2, 4, 0),
+    ] {
+        let mut keys: HashMap<Vec<String>, StateKey> = HashMap::new();
+        let mut classes: HashMap<StateKey, Vec<String>> = HashMap::new();
+        for state in reachable(n_colors, n_empty, capacity, seed) {
+            let canonical = brute_force_canonical(&state);
+            let key = state.key();
+            assert_eq!(*keys.entry(canonical.clone()).or_insert(key.clone()), key);
+            assert_eq!(*classes.entry(key).or_insert(canonical.clone()), canonical);
+        }
+        n_classes += keys.len();
+    }
+    assert!(n_classes > 100);
+}
+
+#[test]
+fn move_to_cord_keeps_the_beaker_order() {
+    let mut state = State::new(PuzzleConfig::new(3, 2, 4));
+    state.generate_with_seed(0);
+    let empty: Vec<_> = (0..state.n_beakers())
+        .filter(|&i| state.beaker(i).is_empty())
+        .collect();
+    let [first, second] = empty[..] else {
+        panic!("{state}");
+    };
+    // 빈 비커 두 개 중 어느 쪽에 부어도 같은 상태가 된다.
+    let mut into_first = state.clone();
+    let to_first = into_first.move_of(0, first).unwrap();
+    into_first.apply(to_first).unwrap();
+    let mut into_second = state.clone();
+    let to_second = into_second.move_of(0, second).unwrap();
+    into_second.apply(to_second).unwrap();
+    assert_eq!(into_first, into_second);
+    assert_ne!(into_first.cord(), into_second.cord());
+
+    assert_eq!(state.move_to(&into_second), Some(to_first));
+    assert_eq!(state.move_to_cord(&into_first.cord()), Some(to_first));
+    assert_eq!(state.move_to_cord(&into_second.cord()), Some(to_second));
+    assert_eq!(state.move_to_cord(&state.cord()), None);
+}
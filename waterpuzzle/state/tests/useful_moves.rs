@@ -0,0 +1,100 @@
+//! 작은 판에서 useful_moves()만 써도 모든 상태에서 목표까지의 가장 짧은 거리가 그대로인지 확인한다.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use state::{
+    config::{PourRule, PuzzleConfig},
+    state::State,
+};
+
+/// (색의 개수, 빈 비커의 개수, 용량)
+const SMALL_BOARDS: [(usize, usize, usize); 4] = [(3, 1, 3), (3, 1, 4), (4, 1, 3), (3, 2, 3)];
+
+fn all_next_states(state: &State) -> Vec<State> {
+    let mut nexts = Vec::new();
+    for i in 0..state.n_beakers() {
+        for j in 0..state.n_beakers() {
+            let mut next = state.clone();
+            if next.pour(i, j).is_ok() {
+                nexts.push(next);
+            }
+        }
+    }
+    nexts
+}
+
+fn useful_next_states(state: &State) -> Vec<State> {
+    state
+        .useful_moves()
+        .map(|mv| {
+            let mut next = state.clone();
+            next.apply(mv).unwrap();
+            next
+        })
+        .collect()
+}
+
+/// states의 각 상태에서 목표까지의 가장 짧은 거리. next_states로 이웃을 만든다.
+fn distances(
+    states: &HashSet<State>,
+    next_states: fn(&State) -> Vec<State>,
+) -> HashMap<State, u32> {
+    let mut predecessors: HashMap<State, Vec<State>> = HashMap::new();
+    for state in states {
+        for next in next_states(state) {
+            predecessors.entry(next).or_default().push(state.clone());
+        }
+    }
+    let mut distance = HashMap::new();
+    let mut queue = VecDeque::new();
+    for state in states.iter().filter(|state| state.is_search_goal()) {
+        distance.insert(state.clone(), 0);
+        queue.push_back(state.clone());
+    }
+    while let Some(state) = queue.pop_front() {
+        let d = distance[&state];
+        for prev in predecessors.get(&state).into_iter().flatten() {
+            if !distance.contains_key(prev) {
+                distance.insert(prev.clone(), d + 1);
+                queue.push_back(prev.clone());
+            }
+        }
+    }
+    distance
+}
+
+#[test]
+fn useful_moves_keep_shortest_distances() {
+    for (n_colors, n_empty, capacity) in SMALL_BOARDS {
+        for rule in PourRule::ALL {
+            for seed in 0..3 {
+                let config = PuzzleConfig::new(n_colors, n_empty, capacity).with_pour_rule(rule);
+                let mut initial = State::new(config);
+                initial.generate_with_seed(seed);
+                let mut states = HashSet::from([initial.clone()]);
+                let mut queue = VecDeque::from([initial]);
+                while let Some(state) = queue.pop_front() {
+                    for next in all_next_states(&state) {
+                        if states.insert(next.clone()) {
+                            queue.push_back(next);
+                        }
+                    }
+                }
+
+                let expected = distances(&states, all_next_states);
+                let actual = distances(&states, useful_next_states);
+                for state in &states {
+                    assert_eq!(actual.get(state), expected.get(state), "{state} ({rule})");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn prunes_equivalent_moves() {
+    // 완성된 비커, 한 색만 든 비커를 빈 비커로 옮기기, 두 번째 빈 비커는 빠진다.
+    let state: State = "RRR|BB.|YYB|Y..|...|...".parse().unwrap();
+    let moves: Vec<_> = state.useful_moves().map(|mv| (mv.from, mv.to)).collect();
+    assert_eq!(moves, vec![(2, 1), (2, 4)]);
+}